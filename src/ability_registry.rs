@@ -0,0 +1,237 @@
+/*
+This module reads the ability registry, the file describing every special move a combatant can be given: what it
+costs, what it can be aimed at, how far it reaches, how long before it can be used again and the list of effects it
+has on whatever it hits. Combatants only hold the keys of the abilities they know, so a new move is just a new line
+in the file
+*/
+use crate::action_economy::*;
+use crate::components::*;
+use crate::dice::Dice;
+use macroquad::prelude::*;
+
+///where the ability registry lives, relative to the resources folder
+pub const ABILITIES_PATH: &str = "abilities.def";
+
+///What an ability can be pointed at
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AbilityTargeting {
+    ///only ever affects whoever uses it, so there's nothing to aim
+    SelfOnly,
+    ///any tile that can be seen
+    Tile,
+    ///a combatant, which can be the user
+    Entity,
+    ///every tile within the radius of the tile it's aimed at
+    Area { radius: i32 },
+}
+impl AbilityTargeting {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text.split_whitespace().collect::<Vec<&str>>().as_slice() {
+            ["self"] => Ok(AbilityTargeting::SelfOnly),
+            ["tile"] => Ok(AbilityTargeting::Tile),
+            ["entity"] => Ok(AbilityTargeting::Entity),
+            ["area", radius] => radius
+                .parse::<i32>()
+                .ok()
+                .filter(|radius| *radius >= 0)
+                .map(|radius| AbilityTargeting::Area { radius })
+                .ok_or_else(|| format!("invalid area radius \"{}\"", radius)),
+            _ => Err(format!("unknown targeting \"{}\"", text)),
+        }
+    }
+    pub fn label(&self) -> String {
+        match self {
+            AbilityTargeting::SelfOnly => String::from("self"),
+            AbilityTargeting::Tile => String::from("tile"),
+            AbilityTargeting::Entity => String::from("target"),
+            AbilityTargeting::Area { radius } => format!("area {}", radius),
+        }
+    }
+}
+
+///One step of what an ability does, applied in order to everyone it reaches
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AbilityEffect {
+    Damage(Dice),
+    Heal(Dice),
+    ///puts a status effect on them for a number of rounds
+    AddStatus(StatusEffect, i32),
+    RemoveStatus(StatusEffect),
+    ///leaves a hazard over every tile the ability reaches for a number of rounds
+    Hazard(HazardKind, i32),
+}
+impl AbilityEffect {
+    ///reads an effect written as "damage 1d6", "heal 1d4+1", "status stunned 1", "cleanse stunned" or "hazard smoke 2"
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let rounds = |rounds: &str| {
+            rounds
+                .parse::<i32>()
+                .ok()
+                .filter(|rounds| *rounds > 0)
+                .ok_or_else(|| format!("invalid number of rounds \"{}\"", rounds))
+        };
+        let status = |key: &str| {
+            StatusEffect::parse(key).ok_or_else(|| format!("unknown status effect \"{}\"", key))
+        };
+        match text.split_whitespace().collect::<Vec<&str>>().as_slice() {
+            ["damage", dice] => Ok(AbilityEffect::Damage(Dice::parse(dice)?)),
+            ["heal", dice] => Ok(AbilityEffect::Heal(Dice::parse(dice)?)),
+            ["status", key, length] => Ok(AbilityEffect::AddStatus(status(key)?, rounds(length)?)),
+            ["cleanse", key] => Ok(AbilityEffect::RemoveStatus(status(key)?)),
+            ["hazard", key, length] => Ok(AbilityEffect::Hazard(
+                HazardKind::parse(key).ok_or_else(|| format!("unknown hazard \"{}\"", key))?,
+                rounds(length)?,
+            )),
+            _ => Err(format!("unknown effect \"{}\"", text)),
+        }
+    }
+}
+
+///Everything about an ability
+#[derive(Clone, PartialEq, Debug)]
+pub struct AbilityDef {
+    pub key: String,
+    pub name: String,
+    pub cost: ActionCost,
+    pub targeting: AbilityTargeting,
+    ///furthest away in tiles it can be aimed
+    pub range: i32,
+    ///rounds before it can be used again
+    pub cooldown: i32,
+    pub effects: Vec<AbilityEffect>,
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct AbilityRegistry {
+    abilities: Vec<AbilityDef>,
+}
+impl AbilityRegistry {
+    ///Reads the registry out of its text. Lines that can't be understood are skipped and described in the returned
+    ///list of errors so one typo doesn't take every other ability with it
+    pub fn parse(text: &str) -> (Self, Vec<String>) {
+        let mut abilities: Vec<AbilityDef> = Vec::new();
+        let mut errors: Vec<String> = Vec::new();
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let result = parse_ability(line).and_then(|ability| {
+                if abilities.iter().any(|existing| existing.key == ability.key) {
+                    Err(format!("ability \"{}\" is listed twice", ability.key))
+                } else {
+                    abilities.push(ability);
+                    Ok(())
+                }
+            });
+            if let Err(error) = result {
+                errors.push(format!(
+                    "{} line {}: {}",
+                    ABILITIES_PATH,
+                    line_number + 1,
+                    error
+                ));
+            }
+        }
+        (Self { abilities }, errors)
+    }
+    pub fn get(&self, key: &str) -> Option<&AbilityDef> {
+        self.abilities.iter().find(|ability| ability.key == key)
+    }
+}
+
+///parses an ability line written as "ability | key | name | cost | targeting | range | cooldown | effect, effect, ..."
+fn parse_ability(line: &str) -> Result<AbilityDef, String> {
+    let fields: Vec<&str> = line.split('|').map(|field| field.trim()).collect();
+    let ["ability", key, name, cost, targeting, range, cooldown, effects] = fields.as_slice()
+    else {
+        return Err(format!(
+            "expected \"ability | key | name | cost | targeting | range | cooldown | effects\" but found \"{}\"",
+            line
+        ));
+    };
+    if key.is_empty() || name.is_empty() {
+        return Err(String::from("ability key and name can't be empty"));
+    }
+    let cost = match cost.split_whitespace().collect::<Vec<&str>>().as_slice() {
+        ["free"] => ActionCost::free(),
+        [ap] => parse_cost(ap, None)?,
+        [ap, kind] => parse_cost(ap, Some(kind))?,
+        _ => return Err(format!("invalid cost \"{}\"", cost)),
+    };
+    let range = range
+        .parse::<i32>()
+        .ok()
+        .filter(|range| *range >= 0)
+        .ok_or_else(|| format!("invalid range \"{}\"", range))?;
+    let cooldown = cooldown
+        .parse::<i32>()
+        .ok()
+        .filter(|cooldown| *cooldown >= 0)
+        .ok_or_else(|| format!("invalid cooldown \"{}\"", cooldown))?;
+    let effects = effects
+        .split(',')
+        .map(|effect| effect.trim())
+        .filter(|effect| !effect.is_empty())
+        .map(AbilityEffect::parse)
+        .collect::<Result<Vec<AbilityEffect>, String>>()?;
+    if effects.is_empty() {
+        return Err(String::from("an ability needs at least one effect"));
+    }
+    Ok(AbilityDef {
+        key: key.to_string(),
+        name: name.to_string(),
+        cost,
+        targeting: AbilityTargeting::parse(targeting)?,
+        range,
+        cooldown,
+        effects,
+    })
+}
+
+///Loads the ability registry out of the resources folder. Without it nobody has any abilities to use
+pub async fn load_ability_registry() -> (AbilityRegistry, Vec<String>) {
+    match load_string(ABILITIES_PATH).await {
+        Ok(text) => AbilityRegistry::parse(&text),
+        Err(error) => (
+            AbilityRegistry::default(),
+            vec![format!("couldn't load {}: {}", ABILITIES_PATH, error)],
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_an_ability_line() {
+        let (registry, errors) = AbilityRegistry::parse(
+            "ability | first_aid | First Aid | 1 bonus | entity | 1 | 3 | heal 1d4+1, cleanse stunned\n",
+        );
+        assert!(errors.is_empty());
+        let ability = registry.get("first_aid").unwrap();
+        assert_eq!(ability.name, "First Aid");
+        assert_eq!(ability.cost, ActionCost::bonus(1));
+        assert_eq!(ability.targeting, AbilityTargeting::Entity);
+        assert_eq!(ability.range, 1);
+        assert_eq!(ability.cooldown, 3);
+        assert_eq!(
+            ability.effects,
+            vec![
+                AbilityEffect::Heal(Dice::new(1, 4, 1)),
+                AbilityEffect::RemoveStatus(StatusEffect::Stunned),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_broken_and_repeated_abilities() {
+        let (registry, errors) = AbilityRegistry::parse(
+            "ability | hunker | Hunker Down | 1 | self | 0 | 2 | status hunkered 1\nability | hunker | Again | 1 | self | 0 | 2 | status hunkered 1\nability | blast | Blast | 2 | area -1 | 4 | 2 | damage 1d6\nability | nothing | Nothing | 1 | self | 0 | 0 |\n",
+        );
+        assert_eq!(errors.len(), 3);
+        assert_eq!(registry.get("hunker").unwrap().name, "Hunker Down");
+        assert!(registry.get("blast").is_none());
+    }
+}
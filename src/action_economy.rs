@@ -0,0 +1,233 @@
+/*
+This module reads the action economy, the file saying what everything a combatant can do on their turn costs, which
+actions are bonus or free actions, how many bonus actions everyone gets and how status effects change the AP someone
+starts the round with. Keeping it in a file means a different economy can be tried out without recompiling
+*/
+use crate::components::*;
+use macroquad::prelude::*;
+use std::collections::HashMap;
+
+///where the action economy lives, relative to the resources folder
+pub const ACTIONS_PATH: &str = "actions.def";
+
+///Everything a combatant can spend their turn on
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    Move,
+    Shoot,
+    Overwatch,
+    Melee,
+    Throw,
+    Reload,
+    CycleFireMode,
+    SwapWeapon,
+    PickUp,
+    Equip,
+    Drop,
+}
+impl Action {
+    pub const ALL: [Action; 11] = [
+        Action::Move,
+        Action::Shoot,
+        Action::Overwatch,
+        Action::Melee,
+        Action::Throw,
+        Action::Reload,
+        Action::CycleFireMode,
+        Action::SwapWeapon,
+        Action::PickUp,
+        Action::Equip,
+        Action::Drop,
+    ];
+    ///the name the action goes by in the actions file
+    pub fn key(&self) -> &'static str {
+        match self {
+            Action::Move => "move",
+            Action::Shoot => "shoot",
+            Action::Overwatch => "overwatch",
+            Action::Melee => "melee",
+            Action::Throw => "throw",
+            Action::Reload => "reload",
+            Action::CycleFireMode => "fire_mode",
+            Action::SwapWeapon => "swap_weapon",
+            Action::PickUp => "pick_up",
+            Action::Equip => "equip",
+            Action::Drop => "drop",
+        }
+    }
+    pub fn parse(key: &str) -> Option<Self> {
+        Action::ALL.into_iter().find(|action| action.key() == key)
+    }
+    ///what the action costs when the actions file doesn't say otherwise
+    pub fn default_cost(&self) -> ActionCost {
+        match self {
+            Action::Shoot | Action::Overwatch | Action::Melee | Action::Throw => {
+                ActionCost::standard(2)
+            }
+            Action::Move | Action::Reload | Action::PickUp | Action::Equip => {
+                ActionCost::standard(1)
+            }
+            Action::SwapWeapon => ActionCost::bonus(1),
+            Action::CycleFireMode | Action::Drop => ActionCost::free(),
+        }
+    }
+}
+
+///how many bonus actions everyone gets each round when the actions file doesn't say otherwise
+pub const DEFAULT_BONUS_ACTIONS: i32 = 1;
+
+///What everything costs and how status effects change the AP pool
+#[derive(Clone, PartialEq, Debug)]
+pub struct ActionEconomy {
+    costs: HashMap<Action, ActionCost>,
+    ap_modifiers: HashMap<StatusEffect, i32>,
+    bonus_actions: i32,
+}
+impl Default for ActionEconomy {
+    fn default() -> Self {
+        Self {
+            costs: Action::ALL
+                .into_iter()
+                .map(|action| (action, action.default_cost()))
+                .collect(),
+            ap_modifiers: StatusEffect::ALL
+                .into_iter()
+                .map(|status| (status, status.default_ap_modifier()))
+                .collect(),
+            bonus_actions: DEFAULT_BONUS_ACTIONS,
+        }
+    }
+}
+impl ActionEconomy {
+    ///Reads the action economy out of its text. Anything the file doesn't mention keeps its default and lines that
+    ///can't be understood are skipped and described in the returned list of errors
+    pub fn parse(text: &str) -> (Self, Vec<String>) {
+        let mut economy = Self::default();
+        let mut errors: Vec<String> = Vec::new();
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Err(error) = economy.parse_line(line) {
+                errors.push(format!(
+                    "{} line {}: {}",
+                    ACTIONS_PATH,
+                    line_number + 1,
+                    error
+                ));
+            }
+        }
+        (economy, errors)
+    }
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let fields: Vec<&str> = line.split('|').map(|field| field.trim()).collect();
+        match fields.as_slice() {
+            ["action", key, cost] | ["action", key, cost, _] => {
+                let action =
+                    Action::parse(key).ok_or_else(|| format!("unknown action \"{}\"", key))?;
+                let cost = parse_cost(cost, fields.get(3).copied())?;
+                self.costs.insert(action, cost);
+                Ok(())
+            }
+            ["effect", key, modifier] => {
+                let status = StatusEffect::parse(key)
+                    .ok_or_else(|| format!("unknown status effect \"{}\"", key))?;
+                let modifier = modifier
+                    .parse::<i32>()
+                    .map_err(|_| format!("invalid AP modifier \"{}\"", modifier))?;
+                self.ap_modifiers.insert(status, modifier);
+                Ok(())
+            }
+            ["bonus_actions", count] => {
+                self.bonus_actions = count
+                    .parse::<i32>()
+                    .ok()
+                    .filter(|count| *count >= 0)
+                    .ok_or_else(|| format!("invalid number of bonus actions \"{}\"", count))?;
+                Ok(())
+            }
+            _ => Err(format!(
+                "expected \"action | name | AP cost\", \"effect | status | AP modifier\" or \"bonus_actions | count\" but found \"{}\"",
+                line
+            )),
+        }
+    }
+    pub fn cost(&self, action: Action) -> ActionCost {
+        self.costs
+            .get(&action)
+            .copied()
+            .unwrap_or_else(|| action.default_cost())
+    }
+    ///how much the status effects someone is under change the AP they start the round with
+    pub fn ap_modifier(&self, status: &StatusEffects) -> i32 {
+        status
+            .get()
+            .iter()
+            .map(|status| self.ap_modifiers.get(status).copied().unwrap_or(0))
+            .sum()
+    }
+    pub fn bonus_actions(&self) -> i32 {
+        self.bonus_actions
+    }
+}
+
+///Reads an AP cost along with an optional "bonus" or "free", which is how costs are written in the data files
+pub fn parse_cost(ap: &str, kind: Option<&str>) -> Result<ActionCost, String> {
+    let ap = ap
+        .parse::<i32>()
+        .ok()
+        .filter(|ap| *ap >= 0)
+        .ok_or_else(|| format!("invalid AP cost \"{}\"", ap))?;
+    match kind {
+        None => Ok(ActionCost::standard(ap)),
+        Some("bonus") => Ok(ActionCost::bonus(ap)),
+        Some("free") if ap == 0 => Ok(ActionCost::free()),
+        Some("free") => Err(String::from("free actions can't cost AP")),
+        Some(other) => Err(format!("unknown action kind \"{}\"", other)),
+    }
+}
+
+///Loads the action economy out of the resources folder, falling back to the defaults if it can't be found
+pub async fn load_action_economy() -> (ActionEconomy, Vec<String>) {
+    match load_string(ACTIONS_PATH).await {
+        Ok(text) => ActionEconomy::parse(&text),
+        Err(error) => (
+            ActionEconomy::default(),
+            vec![format!("couldn't load {}: {}", ACTIONS_PATH, error)],
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_costs_kinds_and_modifiers() {
+        let (economy, errors) = ActionEconomy::parse(
+            "# a comment\naction | shoot | 3\naction | reload | 1 | bonus\naction | move | 0 | free\nbonus_actions | 2\neffect | hunkered | -1\n",
+        );
+        assert!(errors.is_empty());
+        assert_eq!(economy.cost(Action::Shoot), ActionCost::standard(3));
+        assert_eq!(economy.cost(Action::Reload), ActionCost::bonus(1));
+        assert_eq!(economy.cost(Action::Move), ActionCost::free());
+        //anything left out keeps its default
+        assert_eq!(economy.cost(Action::Melee), Action::Melee.default_cost());
+        assert_eq!(economy.bonus_actions(), 2);
+        let mut status = StatusEffects::default();
+        status.add(StatusEffect::Hunkered);
+        assert_eq!(economy.ap_modifier(&status), -1);
+    }
+
+    #[test]
+    fn skips_lines_it_cant_understand() {
+        let (economy, errors) = ActionEconomy::parse(
+            "action | dance | 1\naction | shoot | -2\naction | throw | 1 | free\nbonus_actions | lots\naction | melee | 1\n",
+        );
+        assert_eq!(errors.len(), 4);
+        assert_eq!(economy.cost(Action::Shoot), Action::Shoot.default_cost());
+        assert_eq!(economy.cost(Action::Throw), Action::Throw.default_cost());
+        assert_eq!(economy.cost(Action::Melee), ActionCost::standard(1));
+    }
+}
@@ -0,0 +1,183 @@
+/*
+This module reads the asset manifest, the file listing every texture and sound the game loads along with the key
+they're stored under, so new assets can be added without touching the code that loads them
+*/
+use macroquad::prelude::*;
+
+///where the manifest lives, relative to the resources folder
+pub const MANIFEST_PATH: &str = "assets.manifest";
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AssetKind {
+    Texture,
+    Sound,
+}
+
+///One asset listed in the manifest
+#[derive(Clone, PartialEq, Debug)]
+pub struct ManifestEntry {
+    pub kind: AssetKind,
+    pub key: String,
+    pub path: String,
+    ///the part of the texture to use if it's a sprite on a larger sheet
+    pub source: Option<Rect>,
+}
+
+///An animation listed in the manifest, made up of sprites that are already listed as textures
+#[derive(Clone, PartialEq, Debug)]
+pub struct AnimationDef {
+    pub key: String,
+    ///how many seconds each frame is shown for
+    pub frame_time: f32,
+    ///keys of the sprites shown for each frame, in order
+    pub frames: Vec<String>,
+    ///whether the animation starts over once it's done or holds on its last frame
+    pub looping: bool,
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct AssetManifest {
+    pub entries: Vec<ManifestEntry>,
+    pub animations: Vec<AnimationDef>,
+}
+impl AssetManifest {
+    ///Reads a manifest out of its text. Lines that can't be understood are skipped and described in the
+    ///returned list of errors so one typo doesn't stop everything else from loading
+    pub fn parse(text: &str) -> (Self, Vec<String>) {
+        let mut entries: Vec<ManifestEntry> = Vec::new();
+        let mut animations: Vec<AnimationDef> = Vec::new();
+        let mut errors: Vec<String> = Vec::new();
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let result = if line.starts_with("animation") {
+                parse_animation(line).map(|animation| animations.push(animation))
+            } else {
+                parse_entry(line).map(|entry| entries.push(entry))
+            };
+            match result {
+                Ok(()) => {}
+                Err(error) => errors.push(format!(
+                    "{} line {}: {}",
+                    MANIFEST_PATH,
+                    line_number + 1,
+                    error
+                )),
+            }
+        }
+        (
+            Self {
+                entries,
+                animations,
+            },
+            errors,
+        )
+    }
+    pub fn textures(&self) -> impl Iterator<Item = &ManifestEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.kind == AssetKind::Texture)
+    }
+    pub fn sounds(&self) -> impl Iterator<Item = &ManifestEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.kind == AssetKind::Sound)
+    }
+}
+
+fn parse_entry(line: &str) -> Result<ManifestEntry, String> {
+    let fields: Vec<&str> = line.split('|').map(|field| field.trim()).collect();
+    if fields.len() < 3 || fields.len() > 4 {
+        return Err(format!(
+            "expected \"kind | key | path\" with an optional rectangle but found \"{}\"",
+            line
+        ));
+    }
+    let kind = match fields[0] {
+        "texture" => AssetKind::Texture,
+        "sound" => AssetKind::Sound,
+        other => return Err(format!("unknown asset kind \"{}\"", other)),
+    };
+    if fields[1].is_empty() || fields[2].is_empty() {
+        return Err(String::from("asset key and path can't be empty"));
+    }
+    let source = match fields.get(3) {
+        Some(rect) => Some(parse_rect(rect)?),
+        None => None,
+    };
+    if kind == AssetKind::Sound && source.is_some() {
+        return Err(String::from("sounds can't have a sprite rectangle"));
+    }
+    Ok(ManifestEntry {
+        kind,
+        key: fields[1].to_string(),
+        path: fields[2].to_string(),
+        source,
+    })
+}
+
+///parses an animation line written as "animation | key | seconds per frame | frame, frame, ... | optional once"
+fn parse_animation(line: &str) -> Result<AnimationDef, String> {
+    let fields: Vec<&str> = line.split('|').map(|field| field.trim()).collect();
+    if fields.len() < 4 || fields.len() > 5 {
+        return Err(format!(
+            "expected \"animation | key | seconds per frame | frames\" with an optional \"once\" but found \"{}\"",
+            line
+        ));
+    }
+    let frame_time = fields[2]
+        .parse::<f32>()
+        .ok()
+        .filter(|frame_time| *frame_time > 0.0)
+        .ok_or_else(|| format!("invalid frame time \"{}\"", fields[2]))?;
+    let frames: Vec<String> = fields[3]
+        .split(',')
+        .map(|frame| frame.trim().to_string())
+        .filter(|frame| !frame.is_empty())
+        .collect();
+    if fields[1].is_empty() || frames.is_empty() {
+        return Err(String::from("animation key and frames can't be empty"));
+    }
+    let looping = match fields.get(4) {
+        None | Some(&"loop") => true,
+        Some(&"once") => false,
+        Some(other) => return Err(format!("unknown animation mode \"{}\"", other)),
+    };
+    Ok(AnimationDef {
+        key: fields[1].to_string(),
+        frame_time,
+        frames,
+        looping,
+    })
+}
+
+///parses a rectangle written as "x y width height"
+fn parse_rect(text: &str) -> Result<Rect, String> {
+    let numbers: Vec<f32> = text
+        .split_whitespace()
+        .map(|number| number.parse::<f32>())
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|_| format!("couldn't read rectangle \"{}\"", text))?;
+    match numbers.as_slice() {
+        [x, y, w, h] => Ok(Rect::new(*x, *y, *w, *h)),
+        _ => Err(format!(
+            "rectangle \"{}\" should be \"x y width height\"",
+            text
+        )),
+    }
+}
+
+///Loads the manifest out of the resources folder. If it can't be found at all there's nothing else to go on,
+///so an empty manifest is returned and the game runs on placeholders
+pub async fn load_manifest() -> (AssetManifest, Vec<String>) {
+    set_pc_assets_folder("resources");
+    match load_string(MANIFEST_PATH).await {
+        Ok(text) => AssetManifest::parse(&text),
+        Err(error) => (
+            AssetManifest::default(),
+            vec![format!("couldn't load {}: {}", MANIFEST_PATH, error)],
+        ),
+    }
+}
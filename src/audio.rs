@@ -0,0 +1,148 @@
+/*
+This module plays the sound effects that systems ask for through SoundEffect messages. Actually making noise is
+left to a backend so that headless runs can swap in one that doesn't need an audio device at all
+*/
+use crate::config::Config;
+use crate::sound_atlas::SoundAtlas;
+use macroquad::audio::*;
+use std::collections::{HashMap, HashSet};
+
+///Keys of the sound atlas entries played for each game event
+pub mod sounds {
+    pub const TURN_START: &str = "chime";
+    pub const FOOTSTEP: &str = "footstep";
+    pub const UI_CONFIRM: &str = "ui confirm";
+    pub const UI_CANCEL: &str = "ui cancel";
+    pub const ATTACK: &str = "attack";
+    pub const HIT: &str = "hit";
+    pub const MISS: &str = "miss";
+    pub const EXPLOSION: &str = "explosion";
+    pub const DRY_FIRE: &str = "dry fire";
+    pub const RELOAD: &str = "reload";
+    pub const ABILITY: &str = "ability";
+}
+
+///What kind of sound an effect is, each category has its own volume so they can be balanced against each other
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum SoundCategory {
+    Ui,
+    Movement,
+    Combat,
+    Turn,
+}
+impl SoundCategory {
+    pub const ALL: [SoundCategory; 4] = [
+        SoundCategory::Ui,
+        SoundCategory::Movement,
+        SoundCategory::Combat,
+        SoundCategory::Turn,
+    ];
+    pub fn label(&self) -> &'static str {
+        match self {
+            SoundCategory::Ui => "ui",
+            SoundCategory::Movement => "movement",
+            SoundCategory::Combat => "combat",
+            SoundCategory::Turn => "turn",
+        }
+    }
+    pub fn parse(name: &str) -> Option<Self> {
+        SoundCategory::ALL
+            .iter()
+            .find(|category| category.label() == name.to_lowercase())
+            .copied()
+    }
+}
+
+///Something that can actually play a sound out of the sound atlas. Returns false if it couldn't find the sound
+pub trait AudioBackend {
+    fn play(&mut self, atlas: &SoundAtlas, sound: &str, volume: f32) -> bool;
+}
+
+///Backend that plays sounds through macroquad
+pub struct MacroquadAudio;
+impl AudioBackend for MacroquadAudio {
+    fn play(&mut self, atlas: &SoundAtlas, sound: &str, volume: f32) -> bool {
+        match atlas.get(sound) {
+            Some(sound) => {
+                play_sound(
+                    sound,
+                    PlaySoundParams {
+                        looped: false,
+                        volume,
+                    },
+                );
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+///Backend that never makes a sound, used for headless runs and anything else without an audio device.
+///Remembers what it was asked to play so that can still be checked on
+#[derive(Default)]
+pub struct SilentAudio {
+    pub played: Vec<(String, f32)>,
+}
+impl AudioBackend for SilentAudio {
+    fn play(&mut self, _atlas: &SoundAtlas, sound: &str, volume: f32) -> bool {
+        self.played.push((sound.to_string(), volume));
+        true
+    }
+}
+
+///Volume settings and the backend sounds are played through
+pub struct AudioPlayer {
+    backend: Box<dyn AudioBackend>,
+    volumes: HashMap<SoundCategory, f32>,
+    muted: bool,
+    ///sounds that have been asked for but couldn't be found, so each one is only complained about once
+    missing: HashSet<String>,
+}
+impl AudioPlayer {
+    pub fn new(config: &Config) -> Self {
+        let backend: Box<dyn AudioBackend> = if config.silent {
+            Box::new(SilentAudio::default())
+        } else {
+            Box::new(MacroquadAudio)
+        };
+        let mut volumes: HashMap<SoundCategory, f32> = HashMap::new();
+        for category in SoundCategory::ALL.iter() {
+            volumes.insert(*category, 1.0);
+        }
+        for (category, volume) in config.volumes.iter() {
+            volumes.insert(*category, *volume);
+        }
+        Self {
+            backend,
+            volumes,
+            muted: config.muted,
+            missing: HashSet::new(),
+        }
+    }
+    ///Plays a sound at its category's volume. Returns false if it wasn't played because the sound is missing,
+    ///muting or a zero volume doesn't count as a failure
+    pub fn play(&mut self, atlas: &SoundAtlas, category: SoundCategory, sound: &str) -> bool {
+        let volume = self.volume(category);
+        if self.muted || volume <= 0.0 {
+            return true;
+        }
+        self.backend.play(atlas, sound, volume)
+    }
+    ///Remembers a sound that couldn't be played. Returns true the first time it goes missing
+    pub fn note_missing(&mut self, sound: &str) -> bool {
+        self.missing.insert(sound.to_string())
+    }
+    pub fn volume(&self, category: SoundCategory) -> f32 {
+        *self.volumes.get(&category).unwrap_or(&1.0)
+    }
+    pub fn set_volume(&mut self, category: SoundCategory, volume: f32) {
+        self.volumes.insert(category, volume.clamp(0.0, 1.0));
+    }
+    pub fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+    }
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+}
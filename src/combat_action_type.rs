@@ -1,9 +1,354 @@
-use macroquad::prelude::*;
+/*
+This module holds the control states the player moves through while taking a turn and the state machine that moves
+them between them. Every turn starts and ends at None, the other states are each one thing the player is in the middle
+of doing. The machine only knows which moves are allowed and what's owed back if something is called off, the hooks
+that spawn reticules, play sounds and hand out refunds live with the input system
+*/
+use crate::components::ActionCost;
+
 ///Control State enum that's used to determine the flow of player input - decides what key does what when
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum CombatActionType {
     ///Root state from which the user selects what actions they want to take that turn
     None,
     Movement,
+    ///Steering the targeting reticule around to pick something to shoot at
+    Targeting,
+    ///Steering the targeting reticule around to pick where to throw something
+    Throwing,
+    ///Picking which neighbouring tile to make a melee attack on
+    Melee,
+    ///Steering the targeting reticule around to pick what to use an ability on
+    Ability,
+    ///Looking through the gear panel to equip, unequip or drop things
+    Inventory,
     EndTurn,
 }
+impl CombatActionType {
+    ///what the player is doing, for the HUD
+    pub fn label(&self) -> &'static str {
+        match self {
+            CombatActionType::None => "Choosing an action",
+            CombatActionType::Movement => "Moving",
+            CombatActionType::Targeting => "Aiming",
+            CombatActionType::Throwing => "Throwing",
+            CombatActionType::Melee => "Picking a melee target",
+            CombatActionType::Ability => "Aiming an ability",
+            CombatActionType::Inventory => "Checking gear",
+            CombatActionType::EndTurn => "Ending turn",
+        }
+    }
+    ///checks if the player is steering a reticule around in this state
+    pub fn uses_reticule(&self) -> bool {
+        matches!(
+            self,
+            CombatActionType::Targeting | CombatActionType::Throwing | CombatActionType::Ability
+        )
+    }
+}
+
+///Why a state was left
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExitReason {
+    ///the player did what the state was for
+    Completed,
+    ///the player backed out
+    Cancelled,
+    ///something outside the player's control ended it, like being gunned down mid move
+    Interrupted,
+}
+
+///A move from one state to another, handed to the enter and exit hooks
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct StateChange {
+    pub from: CombatActionType,
+    pub to: CombatActionType,
+    ///None when a state is being entered
+    pub reason: Option<ExitReason>,
+    ///what has to be given back to the player because the state was left without anything happening
+    pub refund: Option<ActionCost>,
+}
+
+///The player's turn as a state machine. States are entered from None and always lead back to it, either by being
+///completed, cancelled or interrupted. Whatever was paid to enter a state is handed back if it's cancelled before
+///anything in it is committed
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ControlStateMachine {
+    current: CombatActionType,
+    ///what was paid to get into the current state
+    paid: Option<ActionCost>,
+    ///set once something that can't be refunded has happened in the current state
+    committed: bool,
+}
+impl Default for ControlStateMachine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl ControlStateMachine {
+    pub fn new() -> Self {
+        Self {
+            current: CombatActionType::None,
+            paid: None,
+            committed: false,
+        }
+    }
+    ///the state the player is in right now
+    pub fn current(&self) -> CombatActionType {
+        self.current
+    }
+    pub fn is(&self, state: CombatActionType) -> bool {
+        self.current == state
+    }
+    ///whether anything has happened in the current state that means it can't be refunded
+    pub fn is_committed(&self) -> bool {
+        self.committed
+    }
+    ///what was paid to get into the current state, if anything
+    pub fn paid(&self) -> Option<ActionCost> {
+        self.paid
+    }
+    ///States can only be entered from None, anything else has to go back to None first
+    pub fn can_enter(&self, to: CombatActionType) -> bool {
+        self.current == CombatActionType::None && to != CombatActionType::None
+    }
+    ///Moves into a state, remembering what was paid to get there. Fails without changing anything if the move isn't
+    ///allowed
+    pub fn enter(
+        &mut self,
+        to: CombatActionType,
+        paid: Option<ActionCost>,
+    ) -> Result<StateChange, String> {
+        if !self.can_enter(to) {
+            return Err(format!("can't go from {:?} to {:?}", self.current, to));
+        }
+        let from = self.current;
+        self.current = to;
+        self.paid = paid;
+        self.committed = false;
+        Ok(StateChange {
+            from,
+            to,
+            reason: None,
+            refund: None,
+        })
+    }
+    ///marks that something has happened in the current state, so backing out of it won't refund anything
+    pub fn commit(&mut self) {
+        self.committed = true;
+    }
+    ///Winds the current state back to an earlier point, for when something done in it has been undone. Whatever
+    ///was paid to get in is owed back again if nothing committed is left
+    pub fn rewind(&mut self, paid: Option<ActionCost>, committed: bool) {
+        if self.current != CombatActionType::None {
+            self.paid = paid;
+            self.committed = committed;
+        }
+    }
+    ///the player did what they came to do, nothing is refunded
+    pub fn complete(&mut self) -> Option<StateChange> {
+        self.exit(ExitReason::Completed)
+    }
+    ///the player backed out, anything paid to get in comes back as long as nothing was committed
+    pub fn cancel(&mut self) -> Option<StateChange> {
+        self.exit(ExitReason::Cancelled)
+    }
+    ///something else ended the state, nothing is refunded
+    pub fn interrupt(&mut self) -> Option<StateChange> {
+        self.exit(ExitReason::Interrupted)
+    }
+    ///goes back to None, or returns None if that's where the player already is
+    fn exit(&mut self, reason: ExitReason) -> Option<StateChange> {
+        if self.current == CombatActionType::None {
+            return None;
+        }
+        let refund = match reason {
+            ExitReason::Cancelled if !self.committed => self.paid,
+            _ => None,
+        };
+        let change = StateChange {
+            from: self.current,
+            to: CombatActionType::None,
+            reason: Some(reason),
+            refund,
+        };
+        *self = Self::new();
+        Some(change)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///every control state, to check the transitions between each of them
+    const ALL: [CombatActionType; 8] = [
+        CombatActionType::None,
+        CombatActionType::Movement,
+        CombatActionType::Targeting,
+        CombatActionType::Throwing,
+        CombatActionType::Melee,
+        CombatActionType::Ability,
+        CombatActionType::Inventory,
+        CombatActionType::EndTurn,
+    ];
+
+    type Exit = fn(&mut ControlStateMachine) -> Option<StateChange>;
+
+    fn active_states() -> impl Iterator<Item = CombatActionType> {
+        ALL.into_iter()
+            .filter(|state| *state != CombatActionType::None)
+    }
+
+    #[test]
+    fn starts_in_none() {
+        let machine = ControlStateMachine::new();
+        assert_eq!(machine.current(), CombatActionType::None);
+        assert!(!machine.is_committed());
+    }
+
+    #[test]
+    fn every_state_can_be_entered_from_none() {
+        for state in active_states() {
+            let mut machine = ControlStateMachine::new();
+            let change = machine.enter(state, None).unwrap();
+            assert_eq!(
+                change,
+                StateChange {
+                    from: CombatActionType::None,
+                    to: state,
+                    reason: None,
+                    refund: None,
+                }
+            );
+            assert!(machine.is(state));
+        }
+    }
+
+    #[test]
+    fn none_cannot_be_entered() {
+        let mut machine = ControlStateMachine::new();
+        assert!(machine.enter(CombatActionType::None, None).is_err());
+        assert!(machine.is(CombatActionType::None));
+    }
+
+    #[test]
+    fn states_cannot_be_entered_from_each_other() {
+        for from in active_states() {
+            for to in ALL {
+                let mut machine = ControlStateMachine::new();
+                machine.enter(from, None).unwrap();
+                assert!(machine.enter(to, None).is_err(), "{:?} -> {:?}", from, to);
+                assert!(machine.is(from));
+            }
+        }
+    }
+
+    #[test]
+    fn every_state_exits_to_none_each_way() {
+        let exits: [(Exit, ExitReason); 3] = [
+            (ControlStateMachine::complete, ExitReason::Completed),
+            (ControlStateMachine::cancel, ExitReason::Cancelled),
+            (ControlStateMachine::interrupt, ExitReason::Interrupted),
+        ];
+        for state in active_states() {
+            for (exit, reason) in exits {
+                let mut machine = ControlStateMachine::new();
+                machine.enter(state, None).unwrap();
+                let change = exit(&mut machine).unwrap();
+                assert_eq!(change.from, state);
+                assert_eq!(change.to, CombatActionType::None);
+                assert_eq!(change.reason, Some(reason));
+                assert_eq!(change.refund, None);
+                assert!(machine.is(CombatActionType::None));
+            }
+        }
+    }
+
+    #[test]
+    fn exiting_none_does_nothing() {
+        let mut machine = ControlStateMachine::new();
+        assert_eq!(machine.complete(), None);
+        assert_eq!(machine.cancel(), None);
+        assert_eq!(machine.interrupt(), None);
+        assert!(machine.is(CombatActionType::None));
+    }
+
+    #[test]
+    fn cancelling_before_anything_happens_refunds_the_entry_cost() {
+        let mut machine = ControlStateMachine::new();
+        machine
+            .enter(CombatActionType::Movement, Some(ActionCost::standard(1)))
+            .unwrap();
+        let change = machine.cancel().unwrap();
+        assert_eq!(change.refund, Some(ActionCost::standard(1)));
+    }
+
+    #[test]
+    fn cancelling_after_committing_refunds_nothing() {
+        let mut machine = ControlStateMachine::new();
+        machine
+            .enter(CombatActionType::Movement, Some(ActionCost::standard(1)))
+            .unwrap();
+        machine.commit();
+        assert!(machine.is_committed());
+        assert_eq!(machine.cancel().unwrap().refund, None);
+    }
+
+    #[test]
+    fn completing_or_interrupting_refunds_nothing() {
+        let mut machine = ControlStateMachine::new();
+        machine
+            .enter(CombatActionType::Movement, Some(ActionCost::standard(1)))
+            .unwrap();
+        assert_eq!(machine.complete().unwrap().refund, None);
+        machine
+            .enter(CombatActionType::Movement, Some(ActionCost::standard(1)))
+            .unwrap();
+        assert_eq!(machine.interrupt().unwrap().refund, None);
+    }
+
+    #[test]
+    fn entering_a_state_starts_it_uncommitted() {
+        let mut machine = ControlStateMachine::new();
+        machine.enter(CombatActionType::Movement, None).unwrap();
+        machine.commit();
+        machine.complete();
+        assert!(!machine.is_committed());
+        machine
+            .enter(CombatActionType::Movement, Some(ActionCost::bonus(1)))
+            .unwrap();
+        assert!(!machine.is_committed());
+        assert_eq!(machine.cancel().unwrap().refund, Some(ActionCost::bonus(1)));
+    }
+
+    #[test]
+    fn rewinding_to_before_anything_happened_refunds_again() {
+        let mut machine = ControlStateMachine::new();
+        machine.enter(CombatActionType::Movement, None).unwrap();
+        machine.commit();
+        machine.rewind(Some(ActionCost::standard(1)), false);
+        assert_eq!(machine.paid(), Some(ActionCost::standard(1)));
+        assert_eq!(
+            machine.cancel().unwrap().refund,
+            Some(ActionCost::standard(1))
+        );
+    }
+
+    #[test]
+    fn rewinding_to_a_committed_point_refunds_nothing() {
+        let mut machine = ControlStateMachine::new();
+        machine
+            .enter(CombatActionType::Movement, Some(ActionCost::standard(1)))
+            .unwrap();
+        machine.rewind(Some(ActionCost::standard(1)), true);
+        assert_eq!(machine.cancel().unwrap().refund, None);
+    }
+
+    #[test]
+    fn rewinding_none_does_nothing() {
+        let mut machine = ControlStateMachine::new();
+        machine.rewind(Some(ActionCost::standard(1)), true);
+        assert_eq!(machine, ControlStateMachine::new());
+    }
+}
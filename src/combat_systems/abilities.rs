@@ -0,0 +1,235 @@
+use crate::ability_registry::*;
+use crate::audio::sounds;
+use crate::lib::abilities::*;
+use crate::lib::format::*;
+use crate::lib::skills::*;
+use crate::lib::targeting::*;
+use crate::prelude::*;
+use hecs::*;
+use macroquad::prelude::*;
+
+///Game system that resolves every ability MOI. The cost has already been paid by the time the MOI is sent, so this
+///just runs the ability's effects over everyone it reaches and starts its cooldown
+pub fn system(state: &mut GameState, combat_encounter: &mut CombatEncounter) {
+    let mut uses: Vec<MOIUseAbility> = Vec::new();
+    let mut cmd_buf = CommandBuffer::new();
+    for (id, moi) in state.ecs.query_mut::<&MOIUseAbility>() {
+        uses.push(*moi);
+        cmd_buf.despawn(id);
+    }
+    cmd_buf.run_on(&mut state.ecs);
+    for moi in uses.iter() {
+        let (user, slot, target) = moi.get();
+        resolve_ability(state, combat_encounter, user, slot, target);
+    }
+}
+
+///Uses an ability on a tile, applying each of its effects in order to every combatant on the tiles it reaches
+fn resolve_ability(
+    state: &mut GameState,
+    combat_encounter: &mut CombatEncounter,
+    user: Entity,
+    slot: usize,
+    target: IVec2,
+) {
+    let actor = entity_name(&state.ecs, user);
+    let Some(ability) = ability_in_slot(state, user, slot) else {
+        state.ecs.spawn((DebugLogMessage::new(
+            DebugLevel::Warn,
+            Subsystem::Combat,
+            format_message(
+                "{actor} tried to use the ability in slot {slot} but there isn't one there",
+                &[("actor", actor), ("slot", slot.to_string())],
+            ),
+        ),));
+        return;
+    };
+    let tiles = ability_tiles(state, user, &ability, target);
+    if let Ok(abilities) = state.ecs.query_one_mut::<&mut Abilities>(user) {
+        abilities.start_cooldown(&ability.key, ability.cooldown);
+    }
+    state.ecs.spawn((GameLogMessage::new(
+        LogCategory::Combat,
+        format_message(
+            "{actor} uses {ability}.",
+            &[("actor", actor), ("ability", ability.name.clone())],
+        ),
+    ),));
+    state
+        .ecs
+        .spawn((SoundEffect::new(SoundCategory::Combat, sounds::ABILITY),));
+    let _ = state.ecs.insert_one(user, Flash::attack());
+    for effect in ability.effects.iter() {
+        //whoever's still standing there, someone might have gone down to an earlier effect
+        let affected: Vec<Entity> = combatants_in(state, &tiles)
+            .into_iter()
+            .map(|(target, _tile)| target)
+            .collect();
+        apply_effect(state, combat_encounter, *effect, &affected, &tiles);
+    }
+}
+
+///Applies one effect of an ability to everyone it reaches. Any dice are rolled once for everyone
+fn apply_effect(
+    state: &mut GameState,
+    combat_encounter: &mut CombatEncounter,
+    effect: AbilityEffect,
+    affected: &[Entity],
+    tiles: &[IVec2],
+) {
+    match effect {
+        AbilityEffect::Damage(dice) => {
+            let damage = state.rng.roll(RngStream::Damage, &dice);
+            for target in affected.iter() {
+                super::attack::apply_damage(state, combat_encounter, *target, damage);
+            }
+        }
+        AbilityEffect::Heal(dice) => {
+            let amount = state.rng.roll(RngStream::Damage, &dice);
+            for target in affected.iter() {
+                let Ok(health) = state.ecs.query_one_mut::<&mut Health>(*target) else {
+                    continue;
+                };
+                let health_left = health.heal(amount);
+                state.ecs.spawn((GameLogMessage::new(
+                    LogCategory::Combat,
+                    format_message(
+                        "{target} is healed for {amount} and has {health} health.",
+                        &[
+                            ("target", entity_name(&state.ecs, *target)),
+                            ("amount", amount.to_string()),
+                            ("health", health_left.to_string()),
+                        ],
+                    ),
+                ),));
+            }
+        }
+        AbilityEffect::AddStatus(status, rounds) => {
+            for target in affected.iter() {
+                let Some(rounds) = resist_status(state, *target, status, rounds) else {
+                    continue;
+                };
+                if let Ok((effects, timers)) = state
+                    .ecs
+                    .query_one_mut::<(&mut StatusEffects, &mut StatusTimers)>(*target)
+                {
+                    effects.add(status);
+                    timers.add(status, rounds);
+                    state.ecs.spawn((GameLogMessage::new(
+                        LogCategory::Combat,
+                        format_message(
+                            "{target} is {status}.",
+                            &[
+                                ("target", entity_name(&state.ecs, *target)),
+                                ("status", status.label().to_string()),
+                            ],
+                        ),
+                    ),));
+                }
+            }
+        }
+        AbilityEffect::RemoveStatus(status) => {
+            for target in affected.iter() {
+                let removed = match state
+                    .ecs
+                    .query_one_mut::<(&mut StatusEffects, Option<&mut StatusTimers>)>(*target)
+                {
+                    Ok((effects, timers)) if effects.has(status) => {
+                        effects.remove(status);
+                        if let Some(timers) = timers {
+                            timers.remove(status);
+                        }
+                        true
+                    }
+                    _ => false,
+                };
+                if removed {
+                    state.ecs.spawn((GameLogMessage::new(
+                        LogCategory::Combat,
+                        format_message(
+                            "{target} is no longer {status}.",
+                            &[
+                                ("target", entity_name(&state.ecs, *target)),
+                                ("status", status.label().to_string()),
+                            ],
+                        ),
+                    ),));
+                }
+            }
+        }
+        AbilityEffect::Hazard(kind, rounds) => {
+            super::hazards::spread(state, kind, rounds, tiles);
+        }
+    }
+}
+
+///Gives a target the chance to keep their head when something would stun them, with a Resolve check. Returns how
+///many rounds the status sticks for, or None if they shrug it off. Failing badly leaves them reeling a round longer
+fn resist_status(
+    state: &mut GameState,
+    target: Entity,
+    status: StatusEffect,
+    rounds: i32,
+) -> Option<i32> {
+    if status != StatusEffect::Stunned {
+        return Some(rounds);
+    }
+    let check = skill_check(state, target, Skill::Resolve, 0);
+    state.ecs.spawn((GameLogMessage::new(
+        LogCategory::Combat,
+        format_message(
+            "{target} tries to shake it off with a {skill} check and rolls {roll} against {needed}, {outcome}.",
+            &[
+                ("target", entity_name(&state.ecs, target)),
+                ("skill", check.skill.label().to_string()),
+                ("roll", check.roll.to_string()),
+                ("needed", check.target.to_string()),
+                ("outcome", check.describe().to_string()),
+            ],
+        ),
+    ),));
+    if check.succeeded() {
+        None
+    } else if check.degrees >= -3 {
+        Some(rounds)
+    } else {
+        Some(rounds + 1)
+    }
+}
+
+///Called when a round ends. Every ability cooldown counts down a round
+pub fn tick_round(state: &mut GameState) {
+    for (_id, abilities) in state.ecs.query_mut::<&mut Abilities>() {
+        abilities.tick();
+    }
+}
+
+///Called when a combatant's turn starts. Their timed status effects count down a round and anything that's worn off
+///is taken away, so a status lasting a round is still there for everyone else's turns until its holder is up again
+pub fn tick_statuses(state: &mut GameState, entity: Entity) {
+    let worn_off = match state
+        .ecs
+        .query_one_mut::<(&mut StatusEffects, &mut StatusTimers)>(entity)
+    {
+        Ok((effects, timers)) => {
+            let worn_off = timers.tick();
+            for status in worn_off.iter() {
+                effects.remove(*status);
+            }
+            worn_off
+        }
+        Err(_) => Vec::new(),
+    };
+    for status in worn_off {
+        state.ecs.spawn((GameLogMessage::new(
+            LogCategory::Combat,
+            format_message(
+                "{target} is no longer {status}.",
+                &[
+                    ("target", entity_name(&state.ecs, entity)),
+                    ("status", status.label().to_string()),
+                ],
+            ),
+        ),));
+    }
+}
@@ -0,0 +1,68 @@
+use crate::prelude::*;
+use hecs::*;
+use macroquad::prelude::*;
+
+///Game system that advances every running animation by the time since the last frame and cleans up
+///any flashes that have finished. None of this touches the game logic, which stays on whole tiles
+pub fn system(state: &mut GameState) {
+    let delta_time = get_frame_time();
+    for (_id, visual_pos) in state.ecs.query_mut::<&mut VisualPosition>() {
+        visual_pos.update(delta_time);
+    }
+    //walk while sliding between tiles and go back to idle once there, and drop back to idle after one shot
+    //animations like shooting have played through. Dying holds on its last frame for good
+    for (_id, (animated, renderable, visual_pos)) in
+        state
+            .ecs
+            .query_mut::<(&mut AnimatedSprite, &Renderable, Option<&VisualPosition>)>()
+    {
+        animated.update(delta_time);
+        let sliding = visual_pos.is_some_and(|visual_pos| visual_pos.is_animating());
+        match animated.current() {
+            AnimationName::Idle if sliding => animated.play(AnimationName::Walk),
+            AnimationName::Walk if !sliding => animated.play(AnimationName::Idle),
+            AnimationName::Shoot => {
+                let key = AnimationName::Shoot.key(&renderable.get_sprite());
+                if state
+                    .texture_atlas
+                    .animation_finished(&key, animated.elapsed())
+                {
+                    animated.play(AnimationName::Idle);
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut cmd_buf = CommandBuffer::new();
+    for (id, flash) in state.ecs.query_mut::<&mut Flash>() {
+        flash.update(delta_time);
+        if !flash.is_animating() {
+            cmd_buf.remove_one::<Flash>(id);
+        }
+    }
+    cmd_buf.run_on(&mut state.ecs);
+}
+
+///checks if anything is still animating, which is used to hold off on player input until it's done
+pub fn is_animating(state: &mut GameState) -> bool {
+    state
+        .ecs
+        .query_mut::<&VisualPosition>()
+        .into_iter()
+        .any(|(_id, visual_pos)| visual_pos.is_animating())
+        || state
+            .ecs
+            .query_mut::<&Flash>()
+            .into_iter()
+            .any(|(_id, flash)| flash.is_animating())
+}
+
+///jumps every running animation straight to its end
+pub fn skip(state: &mut GameState) {
+    for (_id, visual_pos) in state.ecs.query_mut::<&mut VisualPosition>() {
+        visual_pos.finish();
+    }
+    for (_id, flash) in state.ecs.query_mut::<&mut Flash>() {
+        flash.finish();
+    }
+}
@@ -0,0 +1,253 @@
+use crate::audio::sounds;
+use crate::lib::format::*;
+use crate::lib::hit_chance::*;
+use crate::lib::inventory::*;
+use crate::lib::targeting::*;
+use crate::prelude::*;
+use hecs::*;
+use macroquad::prelude::*;
+
+///Game system that resolves every attack MOI, rolling to hit and applying damage
+pub fn system(state: &mut GameState, combat_encounter: &mut CombatEncounter) {
+    let mut attacks: Vec<MOIAttack> = Vec::new();
+    let mut cmd_buf = CommandBuffer::new();
+    for (id, moi) in state.ecs.query_mut::<&MOIAttack>() {
+        attacks.push(*moi);
+        cmd_buf.despawn(id);
+    }
+    cmd_buf.run_on(&mut state.ecs);
+    for attack in attacks.iter() {
+        let (attacker, target, kind) = attack.get();
+        make_attack(state, combat_encounter, attacker, target, kind);
+    }
+}
+
+///Makes an attack with one of the attacker's weapons. A ranged weapon uses up the rounds for the mode it's set to and
+///gets a roll to hit for every shot that mode fires, but can't be fired at all without enough rounds loaded
+pub fn make_attack(
+    state: &mut GameState,
+    combat_encounter: &mut CombatEncounter,
+    attacker: Entity,
+    target: Entity,
+    kind: AttackKind,
+) {
+    let shots = match kind {
+        AttackKind::Melee => 1,
+        AttackKind::Ranged => match use_rounds(state, attacker) {
+            Some(shots) => shots,
+            None => return,
+        },
+    };
+    for _shot in 0..shots {
+        //no point carrying on once they're down
+        if state.ecs.get::<&ControlType>(target).is_err() {
+            break;
+        }
+        resolve_attack(state, combat_encounter, attacker, target, kind);
+    }
+}
+
+///Takes the rounds for a shot out of the attacker's magazine and returns how many rolls to hit it gets. If there
+///aren't enough rounds loaded the trigger just clicks, which is logged, and None is returned
+fn use_rounds(state: &mut GameState, attacker: Entity) -> Option<i32> {
+    let mode = selected_fire_mode(&state.ecs, attacker);
+    let Some(weapon) = equipped_ranged_weapon_entity(&state.ecs, attacker) else {
+        return Some(mode.shots());
+    };
+    let actor = entity_name(&state.ecs, attacker);
+    let weapon_name = entity_name(&state.ecs, weapon);
+    let fired = match state.ecs.query_one_mut::<&mut Magazine>(weapon) {
+        Ok(magazine) => magazine.fire(mode.rounds()),
+        //weapons that don't need loading never run out
+        Err(_) => return Some(mode.shots()),
+    };
+    match fired {
+        Ok(0) => {
+            state.ecs.spawn((GameLogMessage::new(
+                LogCategory::Combat,
+                format_message(
+                    "{actor}'s {weapon} has run dry.",
+                    &[("actor", actor), ("weapon", weapon_name)],
+                ),
+            ),));
+            Some(mode.shots())
+        }
+        Ok(_rounds_left) => Some(mode.shots()),
+        Err(loaded) => {
+            state
+                .ecs
+                .spawn((SoundEffect::new(SoundCategory::Combat, sounds::DRY_FIRE),));
+            let template = if loaded == 0 {
+                "{actor} pulls the trigger but the {weapon} is empty."
+            } else {
+                "{actor} pulls the trigger but the {weapon} only has {loaded} rounds loaded."
+            };
+            state.ecs.spawn((GameLogMessage::new(
+                LogCategory::Combat,
+                format_message(
+                    template,
+                    &[
+                        ("actor", actor),
+                        ("weapon", weapon_name),
+                        ("loaded", loaded.to_string()),
+                    ],
+                ),
+            ),));
+            None
+        }
+    }
+}
+
+///Works out whether an attack hits and what happens if it does. The chance to hit and everything that went into it
+///is written to the combat log so the player can see why things turned out the way they did
+pub fn resolve_attack(
+    state: &mut GameState,
+    combat_encounter: &mut CombatEncounter,
+    attacker: Entity,
+    target: Entity,
+    kind: AttackKind,
+) {
+    let actor = entity_name(&state.ecs, attacker);
+    let target_name = entity_name(&state.ecs, target);
+    let Some(inputs) = gather_inputs(state, attacker, target, kind) else {
+        state.ecs.spawn((DebugLogMessage::new(
+            DebugLevel::Warn,
+            Subsystem::Combat,
+            format_message(
+                "{actor} tried to attack {target} but one of them can't be found or has no weapon",
+                &[("actor", actor), ("target", target_name)],
+            ),
+        ),));
+        return;
+    };
+    //being attacked gives away where the attacker is, so the steps that put the target in their reach are locked in
+    crate::lib::systems::clear_move_history(state, target);
+    let chance = hit_chance(&inputs);
+    let roll = state.rng.percentile(RngStream::Attack);
+    let hit = roll < chance.probability;
+    state.ecs.spawn((GameLogMessage::new(
+        LogCategory::Combat,
+        format_message(
+            "{actor} {verb} {target} with a {chance}% chance to hit ({breakdown}).",
+            &[
+                ("actor", actor.clone()),
+                ("verb", kind.verb().to_string()),
+                ("target", target_name.clone()),
+                ("chance", chance.probability.to_string()),
+                ("breakdown", chance.describe()),
+            ],
+        ),
+    ),));
+    state
+        .ecs
+        .spawn((SoundEffect::new(SoundCategory::Combat, sounds::ATTACK),));
+    let _ = state.ecs.insert_one(attacker, Flash::attack());
+    if let Ok(animated) = state.ecs.query_one_mut::<&mut AnimatedSprite>(attacker) {
+        animated.play(AnimationName::Shoot);
+    }
+    if !hit {
+        state
+            .ecs
+            .spawn((SoundEffect::new(SoundCategory::Combat, sounds::MISS),));
+        state.ecs.spawn((GameLogMessage::new(
+            LogCategory::Combat,
+            format_message(
+                "{actor} misses (rolled {roll}).",
+                &[("actor", actor), ("roll", roll.to_string())],
+            ),
+        ),));
+        return;
+    }
+    let damage = match attack_weapon(&state.ecs, attacker, kind) {
+        Some((_accuracy, damage)) => {
+            let rolled = state.rng.roll(RngStream::Damage, &damage);
+            state.ecs.spawn((DebugLogMessage::new(
+                DebugLevel::Trace,
+                Subsystem::Combat,
+                format_message(
+                    "{actor} rolled {damage} on {dice} for damage",
+                    &[
+                        ("actor", actor.clone()),
+                        ("damage", rolled.to_string()),
+                        ("dice", damage.to_string()),
+                    ],
+                ),
+            ),));
+            rolled
+        }
+        None => 0,
+    };
+    apply_damage(state, combat_encounter, target, damage);
+}
+
+///Takes damage off a target and handles them going down if it's enough to finish them
+pub fn apply_damage(
+    state: &mut GameState,
+    combat_encounter: &mut CombatEncounter,
+    target: Entity,
+    damage: i32,
+) {
+    let target_name = entity_name(&state.ecs, target);
+    //armour takes the edge off every hit but something always gets through
+    let damage = match equipped_armour(&state.ecs, target) {
+        Some(armour) if damage > 0 => {
+            let soaked = armour.soak.min(damage - 1).max(0);
+            if soaked > 0 {
+                state.ecs.spawn((GameLogMessage::new(
+                    LogCategory::Combat,
+                    format_message(
+                        "{target}'s armour soaks {soaked} damage.",
+                        &[
+                            ("target", target_name.clone()),
+                            ("soaked", soaked.to_string()),
+                        ],
+                    ),
+                ),));
+            }
+            damage - soaked
+        }
+        _ => damage,
+    };
+    let Ok(health) = state.ecs.query_one_mut::<&mut Health>(target) else {
+        return;
+    };
+    let health_left = health.take_damage(damage);
+    let is_dead = health.is_dead();
+    state
+        .ecs
+        .spawn((SoundEffect::new(SoundCategory::Combat, sounds::HIT),));
+    let _ = state.ecs.insert_one(target, Flash::hit());
+    state.ecs.spawn((GameLogMessage::new(
+        LogCategory::Combat,
+        format_message(
+            "{target} is hit for {damage} damage and has {health} health left.",
+            &[
+                ("target", target_name.clone()),
+                ("damage", damage.to_string()),
+                ("health", health_left.to_string()),
+            ],
+        ),
+    ),));
+    if is_dead {
+        //a downed combatant stops taking turns, blocking movement and being a target, but their body stays on the map.
+        //if it was their turn it's over, otherwise the turn would never be ended and whoever's next would never get
+        //theirs started properly
+        if combat_encounter.next_turn() == Some(target) {
+            super::end_turn::end_current_turn(state, combat_encounter);
+        }
+        combat_encounter.remove_combatant(target);
+        if combat_encounter.check_round_completion() {
+            super::end_turn::start_new_round(state, combat_encounter);
+        }
+        crate::lib::systems::stop_blocking(state, target);
+        let _ = state.ecs.remove_one::<ControlType>(target);
+        let _ = state.ecs.remove_one::<Overwatch>(target);
+        if let Ok(animated) = state.ecs.query_one_mut::<&mut AnimatedSprite>(target) {
+            animated.play(AnimationName::Die);
+        }
+        state.ecs.spawn((GameLogMessage::new(
+            LogCategory::Combat,
+            format_message("{target} goes down!", &[("target", target_name)]),
+        ),));
+    }
+}
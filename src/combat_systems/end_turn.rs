@@ -1,6 +1,7 @@
+use crate::audio::sounds;
+use crate::lib::format::*;
 use crate::prelude::*;
 use hecs::*;
-use macroquad::prelude::*;
 
 pub fn system(state: &mut GameState, combat_encounter: &mut CombatEncounter) {
     //this will process requests to end the turn of the current active entity
@@ -16,24 +17,71 @@ pub fn system(state: &mut GameState, combat_encounter: &mut CombatEncounter) {
     //if there is a request to end the turn of the currently active entity
     //then do so
     if is_moi {
-        state
-            .ecs
-            .spawn((DebugLogMessage::new(String::from("completing entity turn")),));
-        let mut round_has_completed = false;
-        let mut entities_in_combat: Vec<Entity> = Vec::new();
-        for (_id, combat_encounter) in state.ecs.query_mut::<&mut CombatEncounter>() {
-            round_has_completed = combat_encounter.complete_turn();
-            entities_in_combat = combat_encounter.get_all_entities();
-        }
-        if round_has_completed {
-            //if the round has completed then refresh the AP of all entities in the combat encounter
-            for entity in entities_in_combat.iter() {
-                for ap in state.ecs.query_one_mut::<&mut ActionPoints>(entity.clone()) {
-                    ap.reset();
-                }
-            }
-            //additionally in the future when a round has been completed an event will be sent into the ECS to be processed by any entity that
-            //has a round limit, such as smoke from a smoke grenade
+        end_current_turn(state, combat_encounter);
+    }
+}
+
+///Finishes the turn of whoever is up next in the initiative order and hands over to the combatant after them,
+///starting a new round if everyone has gone. Also used when the active combatant goes down in the middle of their
+///own turn, so nobody is left waiting on a turn that'll never be ended
+pub fn end_current_turn(state: &mut GameState, combat_encounter: &mut CombatEncounter) {
+    let actor = match combat_encounter.next_turn() {
+        Some(entity) => entity_name(&state.ecs, entity),
+        None => String::from("Nobody"),
+    };
+    state.ecs.spawn((DebugLogMessage::new(
+        DebugLevel::Debug,
+        Subsystem::Turn,
+        format_message("completing {actor}'s turn", &[("actor", actor.clone())]),
+    ),));
+    state.ecs.spawn((GameLogMessage::new(
+        LogCategory::Turn,
+        format_message("{actor} ends their turn.", &[("actor", actor)]),
+    ),));
+    //flush the logs before the turn ticks over so everything that happened this turn gets stamped with it
+    super::logs::system(state, combat_encounter);
+    //the combat encounter passed in is the one that gets written back into the ECS at the end of the frame
+    //so it's the one that needs to be mutated
+    let round_has_completed = combat_encounter.complete_turn();
+    state.number_turns += 1;
+    //overwatch only lasts until the watcher's next turn comes around
+    if let Some(next) = combat_encounter.next_turn() {
+        if state.ecs.remove_one::<Overwatch>(next).is_ok() {
+            state.ecs.spawn((GameLogMessage::new(
+                LogCategory::Combat,
+                format_message(
+                    "{actor} is no longer on overwatch.",
+                    &[("actor", entity_name(&state.ecs, next))],
+                ),
+            ),));
         }
     }
+    //let everyone know it's the next combatant's turn
+    state
+        .ecs
+        .spawn((SoundEffect::new(SoundCategory::Turn, sounds::TURN_START),));
+    if round_has_completed {
+        start_new_round(state, combat_encounter);
+    }
+    //statuses wear off as their holder's turn comes around, after AP is handed out so a status that costs AP gets
+    //to cost it for the round it's still on
+    if let Some(next) = combat_encounter.next_turn() {
+        super::abilities::tick_statuses(state, next);
+    }
+}
+
+///Everything that happens once everyone has had their turn, called as the next round begins
+pub fn start_new_round(state: &mut GameState, combat_encounter: &mut CombatEncounter) {
+    //refresh the AP of all entities in the combat encounter
+    for entity in combat_encounter.get_all_entities().iter() {
+        crate::lib::systems::refresh_action_points(state, *entity);
+    }
+    state.ecs.spawn((GameLogMessage::new(
+        LogCategory::Turn,
+        format!("Round {} begins.", combat_encounter.get_round()),
+    ),));
+    //smoke and fire burn themselves out a round at a time
+    super::hazards::tick_round(state, combat_encounter);
+    //ability cooldowns count down a round at a time
+    super::abilities::tick_round(state);
 }
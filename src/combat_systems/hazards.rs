@@ -0,0 +1,89 @@
+use crate::lib::format::*;
+use crate::lib::targeting::*;
+use crate::prelude::*;
+use hecs::*;
+use macroquad::prelude::*;
+
+///the dice fire burns anyone standing in it with at the start of every round
+pub const FIRE_DAMAGE: Dice = Dice {
+    count: 1,
+    sides: 4,
+    modifier: 0,
+};
+
+///Game system that keeps everyone's statuses in step with the hazards they're standing in. Anyone in smoke is
+///harder to hit for as long as they stay in it
+pub fn system(state: &mut GameState) {
+    let smoke: Vec<IVec2> = hazard_tiles(state, HazardKind::Smoke);
+    for (_id, (pos, status)) in state.ecs.query_mut::<(&IVec2, &mut StatusEffects)>() {
+        if smoke.contains(pos) {
+            status.add(StatusEffect::InSmoke);
+        } else {
+            status.remove(StatusEffect::InSmoke);
+        }
+    }
+}
+
+///returns every tile covered by a kind of hazard
+pub fn hazard_tiles(state: &GameState, kind: HazardKind) -> Vec<IVec2> {
+    state
+        .ecs
+        .query::<(&Hazard, &IVec2)>()
+        .iter()
+        .filter(|(_id, (hazard, _pos))| hazard.kind == kind)
+        .map(|(_id, (_hazard, pos))| *pos)
+        .collect()
+}
+
+///Leaves a hazard on every tile, replacing any of the same kind that's already there so they don't stack up
+pub fn spread(state: &mut GameState, kind: HazardKind, rounds: i32, tiles: &[IVec2]) {
+    let mut cmd_buf = CommandBuffer::new();
+    for (id, (hazard, pos)) in state.ecs.query::<(&Hazard, &IVec2)>().iter() {
+        if hazard.kind == kind && tiles.contains(pos) {
+            cmd_buf.despawn(id);
+        }
+    }
+    for tile in tiles.iter() {
+        cmd_buf.spawn((Hazard::new(kind, rounds), *tile));
+    }
+    cmd_buf.run_on(&mut state.ecs);
+}
+
+///Called when a round ends. Fire burns everyone standing in it, then every hazard counts down a round and any that
+///have run out are cleared away
+pub fn tick_round(state: &mut GameState, combat_encounter: &mut CombatEncounter) {
+    let fire = hazard_tiles(state, HazardKind::Fire);
+    let burning: Vec<Entity> = combatants_in(state, &fire)
+        .into_iter()
+        .map(|(entity, _tile)| entity)
+        .collect();
+    for entity in burning {
+        state.ecs.spawn((GameLogMessage::new(
+            LogCategory::Combat,
+            format_message(
+                "{target} is burned by the flames.",
+                &[("target", entity_name(&state.ecs, entity))],
+            ),
+        ),));
+        let damage = state.rng.roll(RngStream::Damage, &FIRE_DAMAGE);
+        super::attack::apply_damage(state, combat_encounter, entity, damage);
+    }
+    let mut cmd_buf = CommandBuffer::new();
+    let mut cleared: Vec<HazardKind> = Vec::new();
+    for (id, hazard) in state.ecs.query_mut::<&mut Hazard>() {
+        if hazard.tick() {
+            cmd_buf.despawn(id);
+            if !cleared.contains(&hazard.kind) {
+                cleared.push(hazard.kind);
+            }
+        }
+    }
+    cmd_buf.run_on(&mut state.ecs);
+    for kind in cleared {
+        state.ecs.spawn((DebugLogMessage::new(
+            DebugLevel::Debug,
+            Subsystem::Combat,
+            format!("some {} has died away", kind.label()),
+        ),));
+    }
+}
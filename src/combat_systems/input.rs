@@ -1,98 +1,1314 @@
 use super::CombatEncounter;
+use crate::ability_registry::*;
+use crate::action_economy::*;
+use crate::audio::sounds;
 use crate::combat_action_type::*;
 use crate::gamestate::*;
+use crate::lib::abilities::*;
+use crate::lib::blast::*;
+use crate::lib::control::*;
+use crate::lib::format::*;
+use crate::lib::geometry::*;
+use crate::lib::inventory::*;
+use crate::lib::targeting::*;
 use crate::prelude::*;
+use crate::replay::Command;
 use hecs::*;
 use macroquad::prelude::*;
 
+///how many log messages a single scroll moves the log panel by
+const LOG_SCROLL_STEP: usize = 3;
+///how much a single press of - or = changes the volume by
+const VOLUME_STEP: f32 = 0.1;
+
 pub fn system(state: &mut GameState, combat_encounter: &mut CombatEncounter) {
     //command buffer so we can push log messages w/out fucking w/ the borrow checker as much
     let mut cmd_buf = CommandBuffer::new();
     //first check with the combat encounter to see which entity/character is being currently controlled
-    let active_entity = combat_encounter.next_turn().unwrap();
+    //(there's nobody to control once everyone's gone down)
+    let Some(active_entity) = combat_encounter.next_turn() else {
+        return;
+    };
+
+    //grab a copy of their action points to spend from
+    let Ok(mut action_points) = state
+        .ecs
+        .query_one_mut::<&ActionPoints>(active_entity)
+        .copied()
+    else {
+        return;
+    };
+    //name of the active entity for any messages about what they're doing
+    let actor = entity_name(&state.ecs, active_entity);
+
+    //scrolling the log panel, changing the debug filter and the volume works no matter what the player is in the middle of doing
+    log_panel_input(state);
+    debug_input(state);
+    sound_input(state);
 
-    //make an option to hold the queried action points
-    let mut action_points_query: Option<ActionPoints> = None;
-    for ap in state.ecs.query_one_mut::<&ActionPoints>(active_entity) {
-        action_points_query = Some(*ap);
+    //quitting isn't a command since there's nothing to play back once the game's closed, and it isn't held up by
+    //animations either. A replay can be quit whenever
+    if (state.control_state.is(CombatActionType::None) || state.playback.is_some())
+        && quit_pressed()
+    {
+        state.quitting = true;
+        return;
     }
-    let mut action_points = action_points_query.unwrap();
 
-    match state.control_state {
+    //commands either come from the keyboard or from the replay that's being played back, never both
+    let command = if state.playback.is_some() {
+        playback_input(state, combat_encounter)
+    } else {
+        let command = read_command(state);
+        //everything the player does gets recorded so the run can be played back later
+        if let Some(command) = command {
+            state.replay.record(
+                combat_encounter.get_round(),
+                state.number_turns + 1,
+                command,
+            );
+        }
+        command
+    };
+    if let Some(command) = command {
+        let ap_before = action_points;
+        apply_command(
+            state,
+            &mut cmd_buf,
+            command,
+            active_entity,
+            &actor,
+            &mut action_points,
+        );
+        let spent =
+            action_points.get() < ap_before.get() || action_points.bonus() < ap_before.bonus();
+        if locks_in_moves(command, spent) {
+            crate::lib::systems::clear_move_history(state, active_entity);
+        }
+    }
+    //keep the gear panel's highlight on the list even when things are dropped off the end of it
+    if state.control_state.is(CombatActionType::Inventory) {
+        let last = gear_list(&state.ecs, active_entity).len().saturating_sub(1);
+        state.gear_cursor = state.gear_cursor.min(last);
+    }
+    //add all the buffered log statements to the ECS so they actually show up
+    cmd_buf.run_on(&mut state.ecs);
+    //update the action points of the current active entity so that the deductions in action points actually happen
+    state
+        .ecs
+        .insert_one(active_entity, action_points)
+        .expect("Failed to update action points of active entity.");
+}
+
+///Steps can be taken back until the combatant does something that can't be. Anything other than moving that they
+///spend AP or a bonus action on shows them something or sets off a reaction, dropping gear is free but leaves it where they stood, and
+///ending the turn hands over to someone else
+fn locks_in_moves(command: Command, spent: bool) -> bool {
+    match command {
+        Command::StartMoving | Command::UndoMove => false,
+        Command::ConfirmEndTurn | Command::DropItem(_) => true,
+        _ => spent,
+    }
+}
+
+///checks for shift+q
+fn quit_pressed() -> bool {
+    (is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift))
+        && is_key_pressed(KeyCode::Q)
+}
+
+///Turns this frame's keypresses into a command for whatever the player is in the middle of doing
+fn read_command(state: &mut GameState) -> Option<Command> {
+    //hold off on any gameplay input until everything has finished sliding into place, unless the player skips it with
+    //space
+    if super::animation::is_animating(state) {
+        if is_key_pressed(KeyCode::Space) {
+            super::animation::skip(state);
+        } else {
+            return None;
+        }
+    }
+    match state.control_state.current() {
         CombatActionType::None => {
-            //choose to start moving
             if is_key_pressed(KeyCode::S) {
-                cmd_buf.spawn((DebugLogMessage::new(String::from("S Key has been pressed")),));
-                match action_points.minor_action() {
-                    Ok(ap_left) => {
-                        cmd_buf.spawn((GameLogMessage::new(format!(
-                            "[Entity Name] has decided to move and now has {} AP left!",
-                            ap_left
-                        )),));
-                        state.control_state = CombatActionType::Movement;
-                    }
-                    Err(ap_left) => {
-                        cmd_buf.spawn((DebugLogMessage::new(format!(
-                            "[Entity Name] has decided to move but doesn't have enough AP, with only {} points", 
-                            ap_left
-                        )),));
-                    }
-                }
+                //choose to start moving
+                Some(Command::StartMoving)
+            } else if is_key_pressed(KeyCode::U) {
+                Some(Command::UndoMove)
+            } else if is_key_pressed(KeyCode::Enter) {
+                Some(Command::RequestEndTurn)
+            } else if is_key_pressed(KeyCode::F) {
+                //choose to start aiming
+                Some(Command::StartTargeting)
+            } else if is_key_pressed(KeyCode::O) {
+                //watch all the way around
+                Some(Command::Overwatch)
+            } else if is_key_pressed(KeyCode::A) {
+                //choose someone next to them to hit
+                Some(Command::StartMelee)
+            } else if is_key_pressed(KeyCode::G) {
+                //choose somewhere to throw a grenade
+                Some(Command::StartThrow)
+            } else if is_key_pressed(KeyCode::R) {
+                Some(Command::Reload)
+            } else if is_key_pressed(KeyCode::Tab) {
+                Some(Command::CycleFireMode)
+            } else if is_key_pressed(KeyCode::X) {
+                Some(Command::SwapWeapon)
+            } else if is_key_pressed(KeyCode::P) {
+                Some(Command::PickUp)
+            } else if is_key_pressed(KeyCode::I) {
+                Some(Command::OpenGear)
+            } else {
+                //the number keys use whatever's in that slot of the action bar
+                ability_slot_pressed().map(Command::UseAbility)
             }
-            if is_key_down(KeyCode::LeftShift) && is_key_pressed(KeyCode::Q)
-                || is_key_down(KeyCode::RightShift) && is_key_pressed(KeyCode::Q)
-            {
-                state.quitting = true;
+        }
+        CombatActionType::Inventory => {
+            //moving the highlight up and down the list only changes what's on screen so it isn't a command
+            if is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::Kp8) {
+                state.gear_cursor = state.gear_cursor.saturating_sub(1);
+            } else if is_key_pressed(KeyCode::Down) || is_key_pressed(KeyCode::Kp2) {
+                state.gear_cursor += 1;
             }
-            if is_key_pressed(KeyCode::Enter) {
-                state.control_state = CombatActionType::EndTurn;
+            if is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::I) {
+                Some(Command::CloseGear)
+            } else if is_key_pressed(KeyCode::Enter) {
+                Some(Command::ToggleEquip(state.gear_cursor))
+            } else if is_key_pressed(KeyCode::D) {
+                Some(Command::DropItem(state.gear_cursor))
+            } else {
+                None
             }
         }
-        CombatActionType::Movement => {
+        CombatActionType::Targeting => {
             if is_key_pressed(KeyCode::Escape) {
-                println!("character is ending their movement");
-                state.control_state = CombatActionType::None;
-                //need to refresh the active entity's movement points
-                crate::lib::systems::refresh_mp(state, active_entity);
+                Some(Command::CancelTargeting)
+            } else if is_key_pressed(KeyCode::Enter) {
+                Some(Command::ConfirmTarget)
+            } else if is_key_pressed(KeyCode::O) {
+                //watch the cone pointing toward the reticule
+                Some(Command::Overwatch)
+            } else if is_key_pressed(KeyCode::Tab) {
+                Some(Command::CycleFireMode)
+            } else {
+                get_delta().map(Command::Aim)
             }
-            let delta = get_delta();
-            if delta.is_some() {
-                let mut pos: Option<IVec2> = None;
-                for query_pos in state.ecs.query_one_mut::<&IVec2>(active_entity) {
-                    pos = Some(*query_pos);
-                }
-                state.ecs.spawn((MOIWantsToMove::new(
-                    true,
-                    active_entity,
-                    IVec2::new(
-                        pos.unwrap().x + delta.unwrap().x,
-                        pos.unwrap().y + delta.unwrap().y,
-                    ),
-                ),));
+        }
+        CombatActionType::Throwing => {
+            if is_key_pressed(KeyCode::Escape) {
+                Some(Command::CancelTargeting)
+            } else if is_key_pressed(KeyCode::Enter) {
+                Some(Command::ConfirmThrow)
+            } else {
+                get_delta().map(Command::Aim)
+            }
+        }
+        CombatActionType::Ability => {
+            if is_key_pressed(KeyCode::Escape) {
+                Some(Command::CancelTargeting)
+            } else if is_key_pressed(KeyCode::Enter) {
+                Some(Command::ConfirmAbility)
+            } else {
+                get_delta().map(Command::Aim)
             }
+        }
+        CombatActionType::Melee => {
+            if is_key_pressed(KeyCode::Escape) {
+                Some(Command::CancelMelee)
+            } else {
+                get_delta().map(Command::Strike)
+            }
+        }
+        CombatActionType::Movement => {
             //esc is used to stop moving when the user is done
             if is_key_pressed(KeyCode::Escape) {
-                state.control_state = CombatActionType::None;
+                Some(Command::StopMoving)
+            } else if is_key_pressed(KeyCode::U) {
+                //take back the last step, for when the numpad gets the better of the player
+                Some(Command::UndoMove)
+            } else {
+                get_delta().map(Command::Step)
             }
         }
         CombatActionType::EndTurn => {
             //use y or n to confirm or deny if the player actually wants to end turn.
             if is_key_pressed(KeyCode::Y) {
-                state.ecs.spawn((MOIEndTurn,));
-                state.control_state = CombatActionType::None;
+                Some(Command::ConfirmEndTurn)
+            } else if is_key_pressed(KeyCode::N) {
+                Some(Command::CancelEndTurn)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+///Handles the playback controls and hands over the next recorded command when it's time for it. Space pauses,
+///right arrow or period plays a single command while paused and F toggles fast forward
+fn playback_input(state: &mut GameState, combat_encounter: &CombatEncounter) -> Option<Command> {
+    let animating = super::animation::is_animating(state);
+    let playback = state.playback.as_mut()?;
+    if is_key_pressed(KeyCode::Space) {
+        playback.toggle_pause();
+    }
+    if is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::Period) {
+        playback.step();
+    }
+    if is_key_pressed(KeyCode::F) {
+        playback.toggle_fast_forward();
+    }
+    let fast_forward = playback.is_fast_forward();
+    let entry = playback.next(get_time(), animating && !fast_forward);
+    if playback.just_finished() {
+        state.ecs.spawn((GameLogMessage::new(
+            LogCategory::System,
+            String::from("The replay has finished."),
+        ),));
+    }
+    let entry = entry?;
+    //fast forward doesn't wait for anything to finish sliding around
+    if animating {
+        super::animation::skip(state);
+    }
+    //the same commands in the same order should always land on the same turn, if they don't something isn't deterministic
+    let (round, turn) = (combat_encounter.get_round(), state.number_turns + 1);
+    if (entry.round, entry.turn) != (round, turn) {
+        state.ecs.spawn((DebugLogMessage::new(
+            DebugLevel::Warn,
+            Subsystem::Input,
+            format!(
+                "replay has desynced, {} was recorded on R{} T{} but it's R{} T{}",
+                entry.command.to_line(),
+                entry.round,
+                entry.turn,
+                round,
+                turn
+            ),
+        ),));
+    }
+    Some(entry.command)
+}
+
+///Carries out a command for the active entity. Commands that don't make sense for what the player is currently doing
+///are ignored, which is also what keeps a desynced replay from doing anything too strange
+fn apply_command(
+    state: &mut GameState,
+    cmd_buf: &mut CommandBuffer,
+    command: Command,
+    active_entity: Entity,
+    actor: &str,
+    action_points: &mut ActionPoints,
+) {
+    match (state.control_state.current(), command) {
+        (CombatActionType::None, Command::StartMoving) => {
+            cmd_buf.spawn((DebugLogMessage::new(
+                DebugLevel::Trace,
+                Subsystem::Input,
+                String::from("S Key has been pressed"),
+            ),));
+            //what's paid is held onto so it can be given back if they stop before taking a step
+            match action_points.pay(state.actions.cost(Action::Move)) {
+                Ok(paid) => {
+                    cmd_buf.spawn((GameLogMessage::new(
+                        LogCategory::Movement,
+                        format_message(
+                            "{actor} has decided to move and now has {ap} AP left!",
+                            &[
+                                ("actor", actor.to_string()),
+                                ("ap", action_points.get().to_string()),
+                            ],
+                        ),
+                    ),));
+                    enter(
+                        state,
+                        cmd_buf,
+                        active_entity,
+                        CombatActionType::Movement,
+                        Some(paid),
+                    );
+                }
+                Err(problem) => {
+                    cmd_buf.spawn((SoundEffect::new(SoundCategory::Ui, sounds::UI_CANCEL),));
+                    cmd_buf.spawn((DebugLogMessage::new(
+                        DebugLevel::Info,
+                        Subsystem::Input,
+                        format_message(
+                            "{actor} has decided to move but can't, {problem}",
+                            &[("actor", actor.to_string()), ("problem", problem)],
+                        ),
+                    ),));
+                }
+            }
+        }
+        (CombatActionType::None, Command::RequestEndTurn) => {
+            enter(
+                state,
+                cmd_buf,
+                active_entity,
+                CombatActionType::EndTurn,
+                None,
+            );
+        }
+        (CombatActionType::None, Command::StartTargeting) => {
+            start_targeting(state, cmd_buf, active_entity, actor);
+        }
+        (CombatActionType::None, Command::Overwatch) => {
+            start_overwatch(
+                state,
+                cmd_buf,
+                active_entity,
+                actor,
+                action_points,
+                Overwatch::all_round(),
+            );
+        }
+        (CombatActionType::None, Command::StartMelee) => {
+            if equipped_melee_weapon(&state.ecs, active_entity).is_some() {
+                enter(state, cmd_buf, active_entity, CombatActionType::Melee, None);
+            } else {
+                cmd_buf.spawn((SoundEffect::new(SoundCategory::Ui, sounds::UI_CANCEL),));
+                cmd_buf.spawn((GameLogMessage::new(
+                    LogCategory::Combat,
+                    format_message(
+                        "{actor} has no melee weapon to fight with.",
+                        &[("actor", actor.to_string())],
+                    ),
+                ),));
             }
-            if is_key_pressed(KeyCode::N) {
-                state.control_state = CombatActionType::None;
+        }
+        (CombatActionType::Melee, Command::Strike(delta)) => {
+            strike(state, cmd_buf, active_entity, actor, action_points, delta);
+        }
+        (CombatActionType::Melee, Command::CancelMelee) => {
+            cancel_state(state, active_entity, action_points);
+        }
+        (CombatActionType::None, Command::Reload) => {
+            reload(state, cmd_buf, active_entity, actor, action_points);
+        }
+        //switching fire modes is free so it can be done while lining up a shot
+        (CombatActionType::None | CombatActionType::Targeting, Command::CycleFireMode) => {
+            cycle_fire_mode(state, cmd_buf, active_entity, actor, action_points);
+        }
+        (CombatActionType::None, Command::SwapWeapon) => {
+            swap_weapon(state, cmd_buf, active_entity, actor, action_points);
+        }
+        (CombatActionType::None, Command::PickUp) => {
+            pick_up_items(state, cmd_buf, active_entity, actor, action_points);
+        }
+        (CombatActionType::None, Command::OpenGear) => {
+            enter(
+                state,
+                cmd_buf,
+                active_entity,
+                CombatActionType::Inventory,
+                None,
+            );
+        }
+        (CombatActionType::Inventory, Command::ToggleEquip(index)) => {
+            toggle_equip(state, cmd_buf, active_entity, actor, action_points, index);
+        }
+        (CombatActionType::Inventory, Command::DropItem(index)) => {
+            drop_gear(state, cmd_buf, active_entity, actor, action_points, index);
+        }
+        (CombatActionType::Inventory, Command::CloseGear) => {
+            cancel_state(state, active_entity, action_points);
+        }
+        (CombatActionType::None, Command::StartThrow) => {
+            start_throw(state, cmd_buf, active_entity, actor);
+        }
+        (CombatActionType::None, Command::UseAbility(slot)) => {
+            use_ability(state, cmd_buf, active_entity, actor, action_points, slot);
+        }
+        (
+            CombatActionType::Targeting | CombatActionType::Throwing | CombatActionType::Ability,
+            _,
+        ) => {
+            targeting_input(state, cmd_buf, command, active_entity, actor, action_points);
+        }
+        (CombatActionType::Movement, Command::StopMoving) => {
+            state.ecs.spawn((DebugLogMessage::new(
+                DebugLevel::Debug,
+                Subsystem::Movement,
+                format_message(
+                    "{actor} is ending their movement",
+                    &[("actor", actor.to_string())],
+                ),
+            ),));
+            //stopping before taking a single step gives back what starting the move cost
+            cancel_state(state, active_entity, action_points);
+        }
+        (CombatActionType::Movement, Command::Step(delta)) => {
+            if let Ok(pos) = state.ecs.get::<&IVec2>(active_entity).map(|pos| *pos) {
+                state
+                    .ecs
+                    .spawn((MOIWantsToMove::new(true, active_entity, pos + delta),));
             }
         }
+        (CombatActionType::None | CombatActionType::Movement, Command::UndoMove) => {
+            undo_move(state, cmd_buf, active_entity, actor, action_points);
+        }
+        (CombatActionType::EndTurn, Command::ConfirmEndTurn) => {
+            state.ecs.spawn((MOIEndTurn,));
+            cmd_buf.spawn((SoundEffect::new(SoundCategory::Ui, sounds::UI_CONFIRM),));
+            complete_state(state, active_entity);
+        }
+        (CombatActionType::EndTurn, Command::CancelEndTurn) => {
+            cancel_state(state, active_entity, action_points);
+        }
+        (control_state, command) => {
+            state.ecs.spawn((DebugLogMessage::new(
+                DebugLevel::Debug,
+                Subsystem::Input,
+                format!(
+                    "ignoring {} while in {:?}",
+                    command.to_line(),
+                    control_state
+                ),
+            ),));
+        }
     }
-    //add all the buffered log statements to the ECS so they actually show up
-    cmd_buf.run_on(&mut state.ecs);
-    //update the action points of the current active entity so that the deductions in action points actually happen
-    state
+}
+
+///Takes back the last thing the active entity did while moving. A move that's been started but not stepped in yet
+///is called off and its cost given back, otherwise the last step is undone along with its movement point. Undoing
+///the first step of a move gives back what was paid to start it too
+fn undo_move(
+    state: &mut GameState,
+    cmd_buf: &mut CommandBuffer,
+    active_entity: Entity,
+    actor: &str,
+    action_points: &mut ActionPoints,
+) {
+    //nothing has happened in the move yet, so backing out of it is all there is to undo
+    if state.control_state.is(CombatActionType::Movement) && !state.control_state.is_committed() {
+        cancel_state(state, active_entity, action_points);
+        return;
+    }
+    let Some(record) = crate::lib::systems::undo_move(state, active_entity) else {
+        cmd_buf.spawn((GameLogMessage::new(
+            LogCategory::Movement,
+            format_message(
+                "{actor} has no steps they can take back.",
+                &[("actor", actor.to_string())],
+            ),
+        ),));
+        cmd_buf.spawn((SoundEffect::new(SoundCategory::Ui, sounds::UI_CANCEL),));
+        return;
+    };
+    cmd_buf.spawn((GameLogMessage::new(
+        LogCategory::Movement,
+        format_message(
+            "{actor} steps back to {tile} and has {mp} movement points left",
+            &[
+                ("actor", actor.to_string()),
+                ("tile", tile_name(record.from)),
+                ("mp", record.movement_points.current().to_string()),
+            ],
+        ),
+    ),));
+    //they're back in the middle of the move the step was part of
+    if state.control_state.is(CombatActionType::None) {
+        let _ = enter_state(state, active_entity, CombatActionType::Movement, None);
+    }
+    match record.move_cost {
+        //that was the first step so nothing from the move is left, calling it off gives back what it cost
+        Some(paid) => {
+            state.control_state.rewind(Some(paid), false);
+            cancel_state(state, active_entity, action_points);
+        }
+        None => {
+            state.control_state.commit();
+            cmd_buf.spawn((SoundEffect::new(SoundCategory::Ui, sounds::UI_CANCEL),));
+        }
+    }
+}
+
+///Spawns the targeting reticule on top of the active entity and switches over to steering it around,
+///as long as they've got a weapon to aim
+fn start_targeting(
+    state: &mut GameState,
+    cmd_buf: &mut CommandBuffer,
+    active_entity: Entity,
+    actor: &str,
+) {
+    let pos = state.ecs.get::<&IVec2>(active_entity).map(|pos| *pos);
+    match (pos, equipped_ranged_weapon(&state.ecs, active_entity)) {
+        (Ok(_pos), Some(_weapon)) => {
+            enter(
+                state,
+                cmd_buf,
+                active_entity,
+                CombatActionType::Targeting,
+                None,
+            );
+        }
+        _ => {
+            cmd_buf.spawn((SoundEffect::new(SoundCategory::Ui, sounds::UI_CANCEL),));
+            cmd_buf.spawn((GameLogMessage::new(
+                LogCategory::Combat,
+                format_message(
+                    "{actor} has nothing to aim with.",
+                    &[("actor", actor.to_string())],
+                ),
+            ),));
+        }
+    }
+}
+
+///Refills the active entity's ranged weapon from their spare ammo, which is a minor action. Nothing is spent if the
+///weapon doesn't need reloading or can't be reloaded
+fn reload(
+    state: &mut GameState,
+    cmd_buf: &mut CommandBuffer,
+    active_entity: Entity,
+    actor: &str,
+    action_points: &mut ActionPoints,
+) {
+    let Some(weapon) = equipped_ranged_weapon_entity(&state.ecs, active_entity) else {
+        return;
+    };
+    let weapon_name = entity_name(&state.ecs, weapon);
+    let Ok(mut magazine) = state.ecs.get::<&Magazine>(weapon).map(|magazine| *magazine) else {
+        cmd_buf.spawn((GameLogMessage::new(
+            LogCategory::Combat,
+            format_message(
+                "{actor}'s {weapon} doesn't need reloading.",
+                &[("actor", actor.to_string()), ("weapon", weapon_name)],
+            ),
+        ),));
+        return;
+    };
+    let problem = if !magazine.can_reload() {
+        magazine.reload().err().map(String::from)
+    } else {
+        action_points
+            .spend(state.actions.cost(Action::Reload))
+            .err()
+    };
+    if let Some(problem) = problem {
+        cmd_buf.spawn((GameLogMessage::new(
+            LogCategory::Combat,
+            format_message(
+                "{actor} can't reload the {weapon}, {problem}.",
+                &[
+                    ("actor", actor.to_string()),
+                    ("weapon", weapon_name),
+                    ("problem", problem),
+                ],
+            ),
+        ),));
+        cmd_buf.spawn((SoundEffect::new(SoundCategory::Ui, sounds::UI_CANCEL),));
+        return;
+    }
+    let _ = magazine.reload();
+    cmd_buf.insert(weapon, (magazine,));
+    cmd_buf.spawn((GameLogMessage::new(
+        LogCategory::Combat,
+        format_message(
+            "{actor} reloads the {weapon} ({loaded}/{capacity}, {spare} spare).",
+            &[
+                ("actor", actor.to_string()),
+                ("weapon", weapon_name),
+                ("loaded", magazine.loaded().to_string()),
+                ("capacity", magazine.capacity().to_string()),
+                ("spare", magazine.spare().to_string()),
+            ],
+        ),
+    ),));
+    cmd_buf.spawn((SoundEffect::new(SoundCategory::Combat, sounds::RELOAD),));
+}
+
+///Switches the active entity's ranged weapon over to its next fire mode
+fn cycle_fire_mode(
+    state: &mut GameState,
+    cmd_buf: &mut CommandBuffer,
+    active_entity: Entity,
+    actor: &str,
+    action_points: &mut ActionPoints,
+) {
+    let (Some(weapon_entity), Some(weapon)) = (
+        equipped_ranged_weapon_entity(&state.ecs, active_entity),
+        equipped_ranged_weapon(&state.ecs, active_entity),
+    ) else {
+        return;
+    };
+    if let Err(problem) = action_points.spend(state.actions.cost(Action::CycleFireMode)) {
+        action_problem(cmd_buf, actor, "switch fire modes", problem);
+        return;
+    }
+    let mode = weapon.next_fire_mode(selected_fire_mode(&state.ecs, active_entity));
+    cmd_buf.insert(weapon_entity, (SelectedFireMode(mode),));
+    cmd_buf.spawn((GameLogMessage::new(
+        LogCategory::Combat,
+        format_message(
+            "{actor} switches the {weapon} to {mode}.",
+            &[
+                ("actor", actor.to_string()),
+                ("weapon", entity_name(&state.ecs, weapon_entity)),
+                ("mode", mode.label().to_lowercase()),
+            ],
+        ),
+    ),));
+    cmd_buf.spawn((SoundEffect::new(SoundCategory::Ui, sounds::UI_CONFIRM),));
+}
+
+///Moves the player into a new control state with a confirming blip. The state machine only lets states be entered
+///from None so a refusal here means something upstream has gone wrong, which gets flagged in the debug log
+fn enter(
+    state: &mut GameState,
+    cmd_buf: &mut CommandBuffer,
+    active_entity: Entity,
+    to: CombatActionType,
+    paid: Option<ActionCost>,
+) {
+    match enter_state(state, active_entity, to, paid) {
+        Ok(()) => {
+            cmd_buf.spawn((SoundEffect::new(SoundCategory::Ui, sounds::UI_CONFIRM),));
+        }
+        Err(problem) => {
+            cmd_buf.spawn((DebugLogMessage::new(
+                DebugLevel::Warn,
+                Subsystem::Input,
+                problem,
+            ),));
+        }
+    }
+}
+
+///Logs why something the player tried to do couldn't be done
+fn action_problem(cmd_buf: &mut CommandBuffer, actor: &str, action: &str, problem: String) {
+    cmd_buf.spawn((GameLogMessage::new(
+        LogCategory::Combat,
+        format_message(
+            "{actor} can't {action}, {problem}.",
+            &[
+                ("actor", actor.to_string()),
+                ("action", action.to_string()),
+                ("problem", problem),
+            ],
+        ),
+    ),));
+    cmd_buf.spawn((SoundEffect::new(SoundCategory::Ui, sounds::UI_CANCEL),));
+}
+
+///Works out what the active entity's action points would be after paying for an action without spending anything yet,
+///so a gear change that fails doesn't cost them
+fn afford(
+    state: &GameState,
+    action_points: &ActionPoints,
+    action: Action,
+) -> Result<ActionPoints, String> {
+    let mut spent = *action_points;
+    spent
+        .spend(state.actions.cost(action))
+        .map(|_ap_left| spent)
+}
+
+///Switches the active entity between their primary and sidearm, which is a minor action
+fn swap_weapon(
+    state: &mut GameState,
+    cmd_buf: &mut CommandBuffer,
+    active_entity: Entity,
+    actor: &str,
+    action_points: &mut ActionPoints,
+) {
+    let result = afford(state, action_points, Action::SwapWeapon).and_then(|spent| {
+        swap_weapons(&mut state.ecs, active_entity).map(|weapon| (spent, weapon))
+    });
+    match result {
+        Ok((spent, weapon)) => {
+            *action_points = spent;
+            let weapon = weapon
+                .map(|weapon| entity_name(&state.ecs, weapon))
+                .unwrap_or(String::from("nothing"));
+            cmd_buf.spawn((GameLogMessage::new(
+                LogCategory::Combat,
+                format_message(
+                    "{actor} swaps to the {weapon}.",
+                    &[("actor", actor.to_string()), ("weapon", weapon)],
+                ),
+            ),));
+            cmd_buf.spawn((SoundEffect::new(SoundCategory::Ui, sounds::UI_CONFIRM),));
+        }
+        Err(problem) => action_problem(cmd_buf, actor, "swap weapons", problem),
+    }
+}
+
+///Picks up everything on the active entity's tile, which is a minor action
+fn pick_up_items(
+    state: &mut GameState,
+    cmd_buf: &mut CommandBuffer,
+    active_entity: Entity,
+    actor: &str,
+    action_points: &mut ActionPoints,
+) {
+    let Ok(pos) = state.ecs.get::<&IVec2>(active_entity).map(|pos| *pos) else {
+        return;
+    };
+    let result = if items_at(&state.ecs, pos).is_empty() {
+        Err(String::from("there's nothing here"))
+    } else {
+        afford(state, action_points, Action::PickUp)
+    };
+    match result {
+        Ok(spent) => {
+            *action_points = spent;
+            for item in pick_up(&mut state.ecs, active_entity, pos) {
+                cmd_buf.spawn((GameLogMessage::new(
+                    LogCategory::Combat,
+                    format_message(
+                        "{actor} picks up the {item}.",
+                        &[
+                            ("actor", actor.to_string()),
+                            ("item", entity_name(&state.ecs, item)),
+                        ],
+                    ),
+                ),));
+            }
+            cmd_buf.spawn((SoundEffect::new(SoundCategory::Ui, sounds::UI_CONFIRM),));
+        }
+        Err(problem) => action_problem(cmd_buf, actor, "pick anything up", problem),
+    }
+}
+
+///Equips the highlighted item if it's being carried or takes it off if it's equipped, which is a minor action
+fn toggle_equip(
+    state: &mut GameState,
+    cmd_buf: &mut CommandBuffer,
+    active_entity: Entity,
+    actor: &str,
+    action_points: &mut ActionPoints,
+    index: usize,
+) {
+    let entry = gear_list(&state.ecs, active_entity).get(index).copied();
+    let result = match entry {
+        Some(GearEntry {
+            item: Some(item), ..
+        }) => afford(state, action_points, Action::Equip).and_then(|spent| {
+            let message = match entry.and_then(|entry| entry.slot) {
+                Some(slot) => unequip(&mut state.ecs, active_entity, slot)
+                    .map(|_item| "{actor} takes off the {item} and stows it."),
+                None => equip(&mut state.ecs, active_entity, item)
+                    .map(|_slot| "{actor} equips the {item}."),
+            }?;
+            Ok((spent, message, item))
+        }),
+        _ => Err(String::from("there's nothing there")),
+    };
+    match result {
+        Ok((spent, message, item)) => {
+            *action_points = spent;
+            cmd_buf.spawn((GameLogMessage::new(
+                LogCategory::Combat,
+                format_message(
+                    message,
+                    &[
+                        ("actor", actor.to_string()),
+                        ("item", entity_name(&state.ecs, item)),
+                    ],
+                ),
+            ),));
+            cmd_buf.spawn((SoundEffect::new(SoundCategory::Ui, sounds::UI_CONFIRM),));
+        }
+        Err(problem) => action_problem(cmd_buf, actor, "change their gear", problem),
+    }
+}
+
+///Drops the highlighted item on the active entity's tile, which is normally free
+fn drop_gear(
+    state: &mut GameState,
+    cmd_buf: &mut CommandBuffer,
+    active_entity: Entity,
+    actor: &str,
+    action_points: &mut ActionPoints,
+    index: usize,
+) {
+    let (Some(item), Ok(pos)) = (
+        gear_list(&state.ecs, active_entity)
+            .get(index)
+            .and_then(|entry| entry.item),
+        state.ecs.get::<&IVec2>(active_entity).map(|pos| *pos),
+    ) else {
+        action_problem(
+            cmd_buf,
+            actor,
+            "drop anything",
+            String::from("there's nothing there"),
+        );
+        return;
+    };
+    if let Err(problem) = action_points.spend(state.actions.cost(Action::Drop)) {
+        action_problem(cmd_buf, actor, "drop anything", problem);
+        return;
+    }
+    drop_item(&mut state.ecs, active_entity, item, pos);
+    cmd_buf.spawn((GameLogMessage::new(
+        LogCategory::Combat,
+        format_message(
+            "{actor} drops the {item} at {tile}.",
+            &[
+                ("actor", actor.to_string()),
+                ("item", entity_name(&state.ecs, item)),
+                ("tile", tile_name(pos)),
+            ],
+        ),
+    ),));
+    cmd_buf.spawn((SoundEffect::new(SoundCategory::Ui, sounds::UI_CANCEL),));
+}
+
+///returns the action bar slot for whichever number key was just pressed, 1 being the first slot
+fn ability_slot_pressed() -> Option<usize> {
+    [
+        KeyCode::Key1,
+        KeyCode::Key2,
+        KeyCode::Key3,
+        KeyCode::Key4,
+        KeyCode::Key5,
+        KeyCode::Key6,
+        KeyCode::Key7,
+        KeyCode::Key8,
+        KeyCode::Key9,
+    ]
+    .iter()
+    .position(|key| is_key_pressed(*key))
+}
+
+///Checks an ability is off cooldown and can be paid for, which is everything that stops it being used before it's
+///been aimed
+fn ability_problem(
+    state: &GameState,
+    active_entity: Entity,
+    action_points: &ActionPoints,
+    ability: &AbilityDef,
+) -> Option<String> {
+    let cooldown = match state.ecs.get::<&Abilities>(active_entity) {
+        Ok(abilities) => abilities.cooldown(&ability.key),
+        Err(_) => 0,
+    };
+    if cooldown > 0 {
+        Some(format!("it needs {} more rounds to recharge", cooldown))
+    } else {
+        action_points.can_afford(ability.cost).err()
+    }
+}
+
+///Uses the ability in a slot of the active entity's action bar. Anything that only affects them goes off straight
+///away, everything else brings up the reticule to aim it
+fn use_ability(
+    state: &mut GameState,
+    cmd_buf: &mut CommandBuffer,
+    active_entity: Entity,
+    actor: &str,
+    action_points: &mut ActionPoints,
+    slot: usize,
+) {
+    let Some(ability) = ability_in_slot(state, active_entity, slot) else {
+        return;
+    };
+    let Ok(pos) = state.ecs.get::<&IVec2>(active_entity).map(|pos| *pos) else {
+        return;
+    };
+    if let Some(problem) = ability_problem(state, active_entity, action_points, &ability) {
+        action_problem(cmd_buf, actor, &format!("use {}", ability.name), problem);
+        return;
+    }
+    if ability.targeting == AbilityTargeting::SelfOnly {
+        let _ = action_points.spend(ability.cost);
+        cmd_buf.spawn((MOIUseAbility::new(active_entity, slot, pos),));
+        return;
+    }
+    enter(
+        state,
+        cmd_buf,
+        active_entity,
+        CombatActionType::Ability,
+        None,
+    );
+    //the reticule needs to know which ability it's aiming
+    if let Some((reticule, _reticule_pos)) = find_reticule(&state.ecs) {
+        let _ = state.ecs.insert_one(reticule, AimingAbility(slot));
+    }
+}
+
+///Uses the ability being aimed on the tile under the reticule if it can reach it, otherwise explains why not
+fn confirm_ability(
+    state: &mut GameState,
+    cmd_buf: &mut CommandBuffer,
+    active_entity: Entity,
+    actor: &str,
+    action_points: &mut ActionPoints,
+    reticule: Entity,
+    reticule_pos: IVec2,
+) {
+    let Some((slot, ability)) = state
         .ecs
-        .insert_one(active_entity, action_points)
-        .expect("Failed to update action points of active entity.");
+        .get::<&AimingAbility>(reticule)
+        .ok()
+        .and_then(|aiming| {
+            ability_in_slot(state, active_entity, aiming.0).map(|ability| (aiming.0, ability))
+        })
+    else {
+        return;
+    };
+    let problem = ability_problem(state, active_entity, action_points, &ability)
+        .or_else(|| target_problem(state, active_entity, &ability, reticule_pos));
+    match problem {
+        None => {
+            let _ = action_points.spend(ability.cost);
+            cmd_buf.spawn((MOIUseAbility::new(active_entity, slot, reticule_pos),));
+            complete_state(state, active_entity);
+        }
+        Some(problem) => action_problem(
+            cmd_buf,
+            actor,
+            &format!("use {} there", ability.name),
+            problem,
+        ),
+    }
+}
+
+///Spawns the targeting reticule on top of the active entity to pick where to throw their throwable,
+///as long as they've got one
+fn start_throw(
+    state: &mut GameState,
+    cmd_buf: &mut CommandBuffer,
+    active_entity: Entity,
+    actor: &str,
+) {
+    let pos = state.ecs.get::<&IVec2>(active_entity).map(|pos| *pos);
+    match (pos, equipped_throwable(&state.ecs, active_entity)) {
+        (Ok(_pos), Some(_throwable)) => {
+            enter(
+                state,
+                cmd_buf,
+                active_entity,
+                CombatActionType::Throwing,
+                None,
+            );
+        }
+        _ => {
+            cmd_buf.spawn((SoundEffect::new(SoundCategory::Ui, sounds::UI_CANCEL),));
+            cmd_buf.spawn((GameLogMessage::new(
+                LogCategory::Combat,
+                format_message(
+                    "{actor} has nothing to throw.",
+                    &[("actor", actor.to_string())],
+                ),
+            ),));
+        }
+    }
+}
+
+///Steers the reticule, confirms the target or cancels aiming.
+///The reticule is despawned either way once the player's done with it
+fn targeting_input(
+    state: &mut GameState,
+    cmd_buf: &mut CommandBuffer,
+    command: Command,
+    active_entity: Entity,
+    actor: &str,
+    action_points: &mut ActionPoints,
+) {
+    let Some((reticule, reticule_pos)) = find_reticule(&state.ecs) else {
+        //nothing to steer, so there's nothing to do but go back to picking an action
+        interrupt_state(state, active_entity);
+        return;
+    };
+    match command {
+        Command::Aim(delta) => {
+            //the reticule floats over everything so it moves without collision
+            cmd_buf.spawn((MOIWantsToMove::new(false, reticule, reticule_pos + delta),));
+        }
+        Command::CancelTargeting => {
+            cancel_state(state, active_entity, action_points);
+        }
+        Command::Overwatch if state.control_state.is(CombatActionType::Targeting) => {
+            let Ok(pos) = state.ecs.get::<&IVec2>(active_entity).map(|pos| *pos) else {
+                return;
+            };
+            let overwatch = Overwatch::toward(reticule_pos - pos);
+            start_overwatch(
+                state,
+                cmd_buf,
+                active_entity,
+                actor,
+                action_points,
+                overwatch,
+            );
+        }
+        Command::ConfirmTarget if state.control_state.is(CombatActionType::Targeting) => {
+            confirm_target(
+                state,
+                cmd_buf,
+                active_entity,
+                actor,
+                action_points,
+                reticule_pos,
+            )
+        }
+        Command::ConfirmAbility if state.control_state.is(CombatActionType::Ability) => {
+            confirm_ability(
+                state,
+                cmd_buf,
+                active_entity,
+                actor,
+                action_points,
+                reticule,
+                reticule_pos,
+            )
+        }
+        Command::ConfirmThrow if state.control_state.is(CombatActionType::Throwing) => {
+            confirm_throw(
+                state,
+                cmd_buf,
+                active_entity,
+                actor,
+                action_points,
+                reticule_pos,
+            )
+        }
+        _ => {}
+    }
+}
+
+///Makes a melee attack on whoever is standing in the neighbouring tile in a direction, which is a significant action.
+///Anything that stops the attack is reported and leaves the player picking a direction
+fn strike(
+    state: &mut GameState,
+    cmd_buf: &mut CommandBuffer,
+    active_entity: Entity,
+    actor: &str,
+    action_points: &mut ActionPoints,
+    delta: IVec2,
+) {
+    let Ok(pos) = state.ecs.get::<&IVec2>(active_entity).map(|pos| *pos) else {
+        return;
+    };
+    let target = combatant_at(state, pos + delta).filter(|target| *target != active_entity);
+    let problem = if target.is_none() {
+        Some(String::from("there's nobody there to hit"))
+    } else {
+        action_points.spend(state.actions.cost(Action::Melee)).err()
+    };
+    match (problem, target) {
+        (None, Some(target)) => {
+            cmd_buf.spawn((MOIAttack::new(active_entity, target, AttackKind::Melee),));
+            complete_state(state, active_entity);
+        }
+        (problem, _) => {
+            cmd_buf.spawn((GameLogMessage::new(
+                LogCategory::Combat,
+                format_message(
+                    "{actor} can't attack there, {problem}.",
+                    &[
+                        ("actor", actor.to_string()),
+                        ("problem", problem.unwrap_or_default()),
+                    ],
+                ),
+            ),));
+            cmd_buf.spawn((SoundEffect::new(SoundCategory::Ui, sounds::UI_CANCEL),));
+        }
+    }
+}
+
+///Throws at the tile under the reticule if it can be reached, which is a significant action. Otherwise explains why not
+fn confirm_throw(
+    state: &mut GameState,
+    cmd_buf: &mut CommandBuffer,
+    active_entity: Entity,
+    actor: &str,
+    action_points: &mut ActionPoints,
+    reticule_pos: IVec2,
+) {
+    let (Ok(pos), Some((_item, throwable))) = (
+        state.ecs.get::<&IVec2>(active_entity).map(|pos| *pos),
+        equipped_throwable(&state.ecs, active_entity),
+    ) else {
+        return;
+    };
+    let problem = if distance(pos, reticule_pos) > throwable.range {
+        Some(String::from("it's too far to throw"))
+    } else if landing_tile(state, pos, reticule_pos) != reticule_pos {
+        Some(String::from("something is in the way"))
+    } else {
+        action_points.spend(state.actions.cost(Action::Throw)).err()
+    };
+    match problem {
+        None => {
+            cmd_buf.spawn((MOIThrow::new(active_entity, reticule_pos),));
+            complete_state(state, active_entity);
+        }
+        Some(problem) => {
+            cmd_buf.spawn((GameLogMessage::new(
+                LogCategory::Combat,
+                format_message(
+                    "{actor} can't throw there, {problem}.",
+                    &[("actor", actor.to_string()), ("problem", problem)],
+                ),
+            ),));
+            cmd_buf.spawn((SoundEffect::new(SoundCategory::Ui, sounds::UI_CANCEL),));
+        }
+    }
+}
+
+///Puts the active entity on overwatch, which is a significant action. Returns true if they went on watch
+fn start_overwatch(
+    state: &mut GameState,
+    cmd_buf: &mut CommandBuffer,
+    active_entity: Entity,
+    actor: &str,
+    action_points: &mut ActionPoints,
+    overwatch: Overwatch,
+) -> bool {
+    let problem = if equipped_ranged_weapon(&state.ecs, active_entity).is_none() {
+        Some(String::from("they have nothing to shoot with"))
+    } else if let Some(problem) = ammo_problem(&state.ecs, active_entity) {
+        Some(problem)
+    } else {
+        action_points
+            .spend(state.actions.cost(Action::Overwatch))
+            .err()
+    };
+    if let Some(problem) = problem {
+        cmd_buf.spawn((GameLogMessage::new(
+            LogCategory::Combat,
+            format_message(
+                "{actor} can't go on overwatch, {problem}.",
+                &[("actor", actor.to_string()), ("problem", problem)],
+            ),
+        ),));
+        cmd_buf.spawn((SoundEffect::new(SoundCategory::Ui, sounds::UI_CANCEL),));
+        return false;
+    }
+    let message = match overwatch.direction() {
+        Some(_direction) => "{actor} goes on overwatch, watching the way they're aiming.",
+        None => "{actor} goes on overwatch, watching all around.",
+    };
+    cmd_buf.spawn((GameLogMessage::new(
+        LogCategory::Combat,
+        format_message(message, &[("actor", actor.to_string())]),
+    ),));
+    cmd_buf.spawn((SoundEffect::new(SoundCategory::Ui, sounds::UI_CONFIRM),));
+    cmd_buf.insert(active_entity, (overwatch,));
+    complete_state(state, active_entity);
+    true
+}
+
+///Takes the shot if there's a valid target under the reticule, otherwise explains why not
+fn confirm_target(
+    state: &mut GameState,
+    cmd_buf: &mut CommandBuffer,
+    active_entity: Entity,
+    actor: &str,
+    action_points: &mut ActionPoints,
+    reticule_pos: IVec2,
+) {
+    let Some(info) = targeting_info(state, active_entity, reticule_pos) else {
+        return;
+    };
+    let problem = if info.target.is_none() {
+        Some(String::from("there's nobody there to aim at"))
+    } else if !info.in_range() {
+        Some(String::from("the target is out of range"))
+    } else if !info.has_line_of_fire() {
+        Some(String::from("something is in the way"))
+    } else if let Some(problem) = ammo_problem(&state.ecs, active_entity) {
+        Some(problem)
+    } else {
+        //taking the shot is a significant action, so this is the last thing that can stop it
+        action_points.spend(state.actions.cost(Action::Shoot)).err()
+    };
+    match (problem, info.target) {
+        (None, Some(target)) => {
+            cmd_buf.spawn((MOIAttack::new(active_entity, target, AttackKind::Ranged),));
+            complete_state(state, active_entity);
+        }
+        (problem, _) => {
+            cmd_buf.spawn((GameLogMessage::new(
+                LogCategory::Combat,
+                format_message(
+                    "{actor} can't take that shot, {problem}.",
+                    &[
+                        ("actor", actor.to_string()),
+                        ("problem", problem.unwrap_or_default()),
+                    ],
+                ),
+            ),));
+            cmd_buf.spawn((SoundEffect::new(SoundCategory::Ui, sounds::UI_CANCEL),));
+        }
+    }
+}
+
+///handles scrolling the game log panel with the mouse wheel or page up/down, jumping back to the newest message with end
+///and saving the log so far with F5
+fn log_panel_input(state: &mut GameState) {
+    let (_wheel_x, wheel_y) = mouse_wheel();
+    if wheel_y > 0.0 || is_key_pressed(KeyCode::PageUp) {
+        state.log.scroll_up(LOG_SCROLL_STEP);
+    }
+    if wheel_y < 0.0 || is_key_pressed(KeyCode::PageDown) {
+        state.log.scroll_down(LOG_SCROLL_STEP);
+    }
+    if is_key_pressed(KeyCode::End) {
+        state.log.scroll_to_bottom();
+    }
+    if is_key_pressed(KeyCode::F5) {
+        super::logs::export(state);
+    }
+}
+
+///F1 steps the debug log filter through each level so it can be turned up while chasing a bug
+fn debug_input(state: &mut GameState) {
+    if is_key_pressed(KeyCode::F1) {
+        state.debug_log.cycle_level();
+        let level = match state.debug_log.min_level() {
+            Some(level) => level.label(),
+            None => "OFF",
+        };
+        //reported in the game log rather than the debug log since it should show up even when it's switched off
+        state.ecs.spawn((GameLogMessage::new(
+            LogCategory::System,
+            format!("Debug log level is now {}.", level),
+        ),));
+    }
+}
+
+///M mutes and unmutes the sound, - and = turn every category down or up a notch
+fn sound_input(state: &mut GameState) {
+    if is_key_pressed(KeyCode::M) {
+        state.audio.toggle_mute();
+        let message = if state.audio.is_muted() {
+            "Sound muted."
+        } else {
+            "Sound unmuted."
+        };
+        state.ecs.spawn((GameLogMessage::new(
+            LogCategory::System,
+            String::from(message),
+        ),));
+    }
+    let step = if is_key_pressed(KeyCode::Minus) {
+        -VOLUME_STEP
+    } else if is_key_pressed(KeyCode::Equal) {
+        VOLUME_STEP
+    } else {
+        return;
+    };
+    let mut volumes: Vec<String> = Vec::new();
+    for category in SoundCategory::ALL.iter() {
+        let volume = state.audio.volume(*category) + step;
+        state.audio.set_volume(*category, volume);
+        volumes.push(format!(
+            "{} {:.0}%",
+            category.label(),
+            state.audio.volume(*category) * 100.0
+        ));
+    }
+    state.ecs.spawn((GameLogMessage::new(
+        LogCategory::System,
+        format!("Volume is now {}.", volumes.join(", ")),
+    ),));
 }
 
 ///generic function for getting a delta from user keypresses, either for selecting a direction
@@ -1,25 +1,85 @@
 use crate::prelude::*;
 use hecs::*;
-use macroquad::prelude::*;
-pub fn system(state: &mut GameState) {
+pub fn system(state: &mut GameState, combat_encounter: &CombatEncounter) {
     debug_log(state);
-    game_log(state);
+    game_log(state, combat_encounter);
 }
+///goes through all debug log messages and hands them to the debug logger, which decides whether they're shown
 fn debug_log(state: &mut GameState) {
-    //make a query that goes through all debug log messages and for now just
-    //prints them out with println
     let mut cmd_buf = CommandBuffer::new();
     for (id, debug_message) in state.ecs.query_mut::<&DebugLogMessage>() {
-        println!("{}", debug_message.0.clone());
+        state.debug_log.log(
+            debug_message.level,
+            debug_message.subsystem,
+            &debug_message.contents,
+        );
         cmd_buf.despawn(id);
     }
     cmd_buf.run_on(&mut state.ecs);
 }
-fn game_log(state: &mut GameState) {
+///moves every game log message spawned this frame into the game log history, stamped with the current round and turn
+fn game_log(state: &mut GameState, combat_encounter: &CombatEncounter) {
     let mut cmd_buf = CommandBuffer::new();
+    let round = combat_encounter.get_round();
+    let turn = state.number_turns + 1;
     for (id, log_message) in state.ecs.query_mut::<&GameLogMessage>() {
-        println!("{}", log_message.0.clone());
+        state.log.push(LogEntry {
+            round,
+            turn,
+            category: log_message.category,
+            text: log_message.contents.clone(),
+        });
         cmd_buf.despawn(id);
     }
     cmd_buf.run_on(&mut state.ecs);
 }
+
+///Writes the game log out to the file set with --combat-log, or if there isn't one to a file named after when it was
+///exported so that earlier exports aren't overwritten. Done whenever the player asks for it and once more when the
+///encounter is over
+pub fn export(state: &mut GameState) {
+    let path = match &state.config.combat_log_file {
+        Some(path) => path.clone(),
+        None => {
+            let timestamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|time| time.as_secs())
+                .unwrap_or(0);
+            format!("combat_log_{}.txt", timestamp)
+        }
+    };
+    let message = match state.log.export(&path) {
+        Ok(()) => format!("Exported the combat log to {}.", path),
+        Err(error) => format!("Failed to export the combat log to {}: {}", path, error),
+    };
+    state
+        .ecs
+        .spawn((GameLogMessage::new(LogCategory::System, message),));
+}
+
+///Saves the replay of this run so it can be played back with --replay. Replays that are being played back aren't
+///saved again since the file they came from already has everything in it
+pub fn export_replay(state: &mut GameState) {
+    if state.playback.is_some() {
+        return;
+    }
+    let path = state.config.record_path.clone().unwrap_or_else(|| {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or(0);
+        format!("replay_{}.txt", timestamp)
+    });
+    match state.replay.save(&path) {
+        Ok(()) => state.debug_log.log(
+            DebugLevel::Info,
+            Subsystem::Input,
+            &format!("saved replay to {}", path),
+        ),
+        Err(error) => state.debug_log.log(
+            DebugLevel::Error,
+            Subsystem::Input,
+            &format!("failed to save replay to {}: {}", path, error),
+        ),
+    }
+}
@@ -1,12 +1,17 @@
+mod abilities;
+mod animation;
+mod attack;
 mod end_turn;
+mod hazards;
 mod input;
 mod logs;
 mod movement;
 mod render;
+mod sound;
+mod throw;
 
 use crate::prelude::*;
 use hecs::*;
-use macroquad::prelude::*;
 //this file is going to run all of the combat systems
 //the goal is to make this AS PORTABLE AS POSSIBLE so it can be seemlessly integrated
 //into the full version of the rpg with MINIMAL EFFORT
@@ -20,19 +25,32 @@ pub fn run(state: &mut GameState) {
     //first get the player's input and get MOIs put into the ecs
     input::system(state, &mut combat_encounter);
     //then process those MOIs and do other systems
-    movement::system(state);
+    movement::system(state, &mut combat_encounter);
+    attack::system(state, &mut combat_encounter);
+    throw::system(state, &mut combat_encounter);
+    abilities::system(state, &mut combat_encounter);
+    hazards::system(state);
     end_turn::system(state, &mut combat_encounter);
     //then render the gamestate onto the screen
-    logs::system(state);
-    render::system(state);
+    sound::system(state);
+    logs::system(state, &combat_encounter);
+    animation::system(state);
+    render::system(state, &combat_encounter);
+    //the sandbox encounter is over once the player quits, so save the log and replay of what happened
+    if state.quitting {
+        logs::export(state);
+        logs::export_replay(state);
+    }
     update_combat_encounter(state, combat_encounter);
 }
 ///Helper function that returns the current Combat Encounter in the ECS
 fn get_combat_encounter(state: &mut GameState) -> Option<CombatEncounter> {
-    for (_id, combat_encounter) in state.ecs.query_mut::<&CombatEncounter>() {
-        return Some(combat_encounter.clone());
-    }
-    return None;
+    state
+        .ecs
+        .query_mut::<&CombatEncounter>()
+        .into_iter()
+        .next()
+        .map(|(_id, combat_encounter)| combat_encounter.clone())
 }
 
 ///Helper function that updates the combat encounter in the ECS w/ the one that you've been mutating
@@ -1,14 +1,17 @@
+use crate::audio::sounds;
+use crate::combat_action_type::*;
+use crate::lib::format::*;
+use crate::lib::reactions::*;
 use crate::prelude::*;
 use hecs::*;
 use macroquad::prelude::*;
-///Game system that will process all movement MOIs
-pub fn system(state: &mut GameState) {
+///Game system that will process all movement MOIs. Anyone on overwatch gets to interrupt a hostile's move with a
+///reaction shot as soon as they step into view
+pub fn system(state: &mut GameState, combat_encounter: &mut CombatEncounter) {
     //make a vec to hold all the MOIs yr gonna be processing
     let mut moving_mois: Vec<MOIWantsToMove> = Vec::new();
     //make a command buffer so you can purge all the MOIs after extracting them (and other utilities idk yet)
     let mut cmd_buffer = CommandBuffer::new();
-    //bool to track if the entity actually moved
-    let mut has_moved = false;
     //query all of the movement MOIs
     for (id, moi) in state.ecs.query_mut::<&MOIWantsToMove>() {
         //transfer them to our outside buffer
@@ -18,45 +21,56 @@ pub fn system(state: &mut GameState) {
     }
     //run the command buffer on the ECS to get rid of the MOIs
     cmd_buffer.run_on(&mut state.ecs);
+    //anyone who's been interrupted by a reaction doesn't get to carry on with the rest of their moves this frame
+    let mut interrupted: Vec<Entity> = Vec::new();
     //now we can actually read through them and modify the state according to their context
     for moi in moving_mois.iter() {
         //deconstruct the current message of intent
         let (collision, entity, destination) = moi.get();
+        if interrupted.contains(&entity) {
+            continue;
+        }
+        let actor = entity_name(&state.ecs, entity);
+        //bool to track if the entity actually moved
+        let mut has_moved = false;
+        //where the entity is moving from, so it can be turned to face the way it's going
+        let origin = state.ecs.get::<&IVec2>(entity).map(|pos| *pos).ok();
         //check if the entity has a pool of movement points (like all PCs and NPCs will) and make sure they have enough
         let mut movement_points_option: Option<MovementPoints> = None;
         if state.ecs.entity(entity).is_ok() {
-            for mp_comp in state.ecs.query_one_mut::<&MovementPoints>(entity) {
-                movement_points_option = Some(mp_comp.clone());
+            if let Ok(mp_comp) = state.ecs.query_one_mut::<&MovementPoints>(entity) {
+                movement_points_option = Some(*mp_comp);
             }
         }
 
         let mut can_move = true;
         //if the character has a movement point component then you need to check if they have enough
-        if movement_points_option.is_some() {
-            can_move = movement_points_option.unwrap().can_move();
+        if let Some(movement_points) = movement_points_option {
+            can_move = movement_points.can_move();
         }
         //only let them move if they actually can move
         if can_move {
             //check if the entity moving has collision (ex. characters do but reticules don't)
             if collision {
-                //make a vec to hold all the possible points that the moving entity could collide with
-                let mut collision_points: Vec<IVec2> = Vec::new();
-                //collect all of the positions of the entities that can be collided with
-                for (_id, collision_pos) in state.ecs.query_mut::<With<&IVec2, &Collideable>>() {
-                    collision_points.push(*collision_pos);
-                }
                 //first check if the entity would be blocked by a map tile
                 if state.map.can_enter_tile(destination) {
-                    //variable to track if the moving entity would collide w/ any others
-                    let mut collides = false;
-                    //then iterate through the collection of positions of other entities the moving entity could possibly collide with
-                    for pos in collision_points.iter() {
-                        if &destination == pos {
-                            collides = true;
+                    //then check the occupancy grid to see if anything that can be collided with is already there
+                    let collides = state.occupancy.is_occupied(destination);
+                    //leaving a hostile's zone of control gives them a free swing before the mover gets away
+                    if let (false, Some(origin)) = (collides, origin) {
+                        if resolve_opportunity_attacks(
+                            state,
+                            combat_encounter,
+                            entity,
+                            origin,
+                            destination,
+                        ) {
+                            interrupted.push(entity);
                         }
                     }
                     //if the entity wouldn't be colliding with any other entity then it's okay to move them!
-                    if !collides {
+                    //(as long as they're still standing after any attacks of opportunity)
+                    if !collides && !is_downed(state, entity) {
                         cmd_buffer.insert(entity, (destination,));
                         cmd_buffer.run_on(&mut state.ecs);
                         has_moved = true;
@@ -67,25 +81,168 @@ pub fn system(state: &mut GameState) {
                 //it's moving to is in bounds of the map
                 if state.map.in_bounds(destination) {
                     cmd_buffer.insert(entity, (destination,));
+                    cmd_buffer.run_on(&mut state.ecs);
                     has_moved = true;
                 }
             }
+            //start sliding the entity's sprite over to its new tile, the logical position has already jumped there
+            if has_moved {
+                if let Some(origin) = origin {
+                    state.occupancy.move_entity(entity, origin, destination);
+                }
+                if let Ok(visual_pos) = state.ecs.query_one_mut::<&mut VisualPosition>(entity) {
+                    visual_pos.move_to(destination, state.config.move_duration);
+                }
+                if let (Some(origin), Ok(facing)) =
+                    (origin, state.ecs.query_one_mut::<&mut Facing>(entity))
+                {
+                    *facing = Facing::from_delta(destination - origin, *facing);
+                }
+                //anything with movement points walks rather than glides so it gets footsteps
+                if movement_points_option.is_some() {
+                    state
+                        .ecs
+                        .spawn((SoundEffect::new(SoundCategory::Movement, sounds::FOOTSTEP),));
+                }
+            }
             //finally, if the character has moved a tile, then if applicable consume one of their movement points
-            if has_moved && movement_points_option.is_some() {
-                let mut movement_points = movement_points_option.unwrap();
+            if let (true, Some(mut movement_points)) = (has_moved, movement_points_option) {
+                //remember how things were before the step so it can be undone, the first step of a move carries what
+                //starting it cost
+                let move_cost = match state.control_state.is_committed() {
+                    true => None,
+                    false => state.control_state.paid(),
+                };
+                if let (Some(origin), Ok(history)) =
+                    (origin, state.ecs.query_one_mut::<&mut MoveHistory>(entity))
+                {
+                    history.push(origin, destination, movement_points, move_cost);
+                }
                 movement_points.decrement();
                 cmd_buffer.insert(entity, (movement_points,));
-                cmd_buffer.spawn((GameLogMessage::new(format!(
-                    "current movement points: {}",
-                    movement_points.current()
-                )),));
+                //a step taken means stopping now won't give back what the move cost
+                if state.control_state.is(CombatActionType::Movement) {
+                    state.control_state.commit();
+                }
+                cmd_buffer.spawn((GameLogMessage::new(
+                    LogCategory::Movement,
+                    format_message(
+                        "{actor} moves to {tile} and has {mp} movement points left",
+                        &[
+                            ("actor", actor.clone()),
+                            ("tile", tile_name(destination)),
+                            ("mp", movement_points.current().to_string()),
+                        ],
+                    ),
+                ),));
                 cmd_buffer.run_on(&mut state.ecs);
+                //only a combatant walking around can be reacted to, not a reticule gliding about
+                if collision && resolve_reactions(state, combat_encounter, entity, destination) {
+                    interrupted.push(entity);
+                }
             }
             //then update the movement points component of the entity that's moving
         } else {
-            cmd_buffer.spawn((GameLogMessage::new(String::from(
-                "Doesn't have enough movement points to move",
-            )),));
+            cmd_buffer.spawn((GameLogMessage::new(
+                LogCategory::Movement,
+                format_message(
+                    "{actor} doesn't have enough movement points to move",
+                    &[("actor", actor)],
+                ),
+            ),));
+            cmd_buffer.run_on(&mut state.ecs);
+        }
+    }
+}
+
+///checks if a combatant has been taken out of the fight, which is when they lose their control type
+fn is_downed(state: &GameState, entity: Entity) -> bool {
+    state.ecs.get::<&Health>(entity).is_ok() && state.ecs.get::<&ControlType>(entity).is_err()
+}
+
+///Gives every hostile whose zone of control the mover is leaving a free melee attack on them. Returns true if anyone
+///attacked
+fn resolve_opportunity_attacks(
+    state: &mut GameState,
+    combat_encounter: &mut CombatEncounter,
+    mover: Entity,
+    from: IVec2,
+    to: IVec2,
+) -> bool {
+    let attackers = opportunity_attackers(state, mover, from, to);
+    if attackers.is_empty() {
+        return false;
+    }
+    let mover_name = entity_name(&state.ecs, mover);
+    for attacker in attackers {
+        if is_downed(state, mover) {
+            break;
+        }
+        state.ecs.spawn((GameLogMessage::new(
+            LogCategory::Combat,
+            format_message(
+                "{mover} leaves {attacker}'s reach and provokes an attack of opportunity!",
+                &[
+                    ("mover", mover_name.clone()),
+                    ("attacker", entity_name(&state.ecs, attacker)),
+                ],
+            ),
+        ),));
+        super::attack::make_attack(state, combat_encounter, attacker, mover, AttackKind::Melee);
+    }
+    if is_downed(state, mover) {
+        crate::lib::control::interrupt_state(state, mover);
+    }
+    true
+}
+
+///Lets everyone watching the tile the mover just stepped onto take their reaction shot, one after the other, before
+///the mover gets to do anything else. Returns true if anybody fired
+fn resolve_reactions(
+    state: &mut GameState,
+    combat_encounter: &mut CombatEncounter,
+    mover: Entity,
+    tile: IVec2,
+) -> bool {
+    let reactors = overwatch_reactors(state, mover, tile);
+    if reactors.is_empty() {
+        return false;
+    }
+    let mover_name = entity_name(&state.ecs, mover);
+    state.ecs.spawn((DebugLogMessage::new(
+        DebugLevel::Debug,
+        Subsystem::Combat,
+        format_message(
+            "{mover}'s move to {tile} is interrupted by {count} reactions",
+            &[
+                ("mover", mover_name.clone()),
+                ("tile", tile_name(tile)),
+                ("count", reactors.len().to_string()),
+            ],
+        ),
+    ),));
+    for watcher in reactors {
+        //the mover might not make it through the first shot, in which case nobody else needs to fire
+        if is_downed(state, mover) {
+            break;
         }
+        //a watcher only gets the one shot before they have to go back on overwatch
+        let _ = state.ecs.remove_one::<Overwatch>(watcher);
+        state.ecs.spawn((GameLogMessage::new(
+            LogCategory::Combat,
+            format_message(
+                "{watcher} reacts to {mover} moving into view!",
+                &[
+                    ("watcher", entity_name(&state.ecs, watcher)),
+                    ("mover", mover_name.clone()),
+                ],
+            ),
+        ),));
+        super::attack::make_attack(state, combat_encounter, watcher, mover, AttackKind::Ranged);
+    }
+    //if the mover was gunned down mid move there's nobody left to keep moving
+    if is_downed(state, mover) {
+        crate::lib::control::interrupt_state(state, mover);
     }
+    true
 }
@@ -1,13 +1,40 @@
+use crate::combat_action_type::*;
+use crate::lib::abilities::*;
+use crate::lib::blast::*;
+use crate::lib::format::*;
+use crate::lib::hit_chance::*;
+use crate::lib::inventory::*;
+use crate::lib::reactions::*;
+use crate::lib::targeting::*;
 use crate::map::*;
 use crate::prelude::*;
+use crate::texture_atlas::*;
 use hecs::*;
 use macroquad::prelude::*;
 
-pub fn system(state: &mut GameState) {
+pub fn system(state: &mut GameState, combat_encounter: &CombatEncounter) {
     //first render the gamemap
     render_map(state);
-    //then render the entities in proper order (the z ordering equivalent is just that they're drawn in the order of the draw_texture function)
+    render_overwatch(state);
+    //anything lying on the floor sits under whoever's standing on it
+    render_items(state);
+    //then render the entities in proper order, sorted by their render layer and z order
     render_entities(state);
+    //smoke and fire hang over whoever's standing in them
+    render_hazards(state);
+    //then the overlays that point things out on the map
+    render_turn_indicator(state, combat_encounter);
+    render_cursor(state);
+    render_targeting(state);
+    render_melee_targets(state, combat_encounter);
+    render_throw(state);
+    render_ability_target(state);
+    //finally draw the ui that sits next to and under the map
+    let initiative_bottom = render_initiative_strip(state, combat_encounter);
+    let action_bar_top = render_action_bar(state, combat_encounter);
+    render_log_panel(state, initiative_bottom, action_bar_top);
+    render_hud(state, combat_encounter);
+    render_gear_panel(state, combat_encounter);
 }
 fn render_map(state: &mut GameState) {
     clear_background(GRAY);
@@ -17,43 +44,845 @@ fn render_map(state: &mut GameState) {
             let pt = IVec2::new(x, y);
             let idx = map_idx(x, y);
             if state.map.in_bounds(pt) {
-                match state.map.tiles[idx] {
-                    TileType::Wall => {
-                        draw_texture(
-                            state.texture_atlas.get("wall").unwrap(),
-                            (x * TILE_WIDTH) as f32,
-                            (y * TILE_HEIGHT) as f32,
-                            WHITE,
-                        );
-                    }
-                    TileType::Floor => {
-                        draw_texture(
-                            state.texture_atlas.get("floor").unwrap(),
-                            (x * TILE_WIDTH) as f32,
-                            (y * TILE_HEIGHT) as f32,
-                            WHITE,
-                        );
-                    }
+                let sprite = match state.map.tiles[idx] {
+                    TileType::Wall => "wall",
+                    TileType::Floor => "floor",
+                    TileType::LowWall => "half cover",
+                };
+                //low walls are drawn standing on a patch of floor
+                if state.map.tiles[idx] == TileType::LowWall {
+                    state.texture_atlas.draw(
+                        "floor",
+                        (x * TILE_WIDTH) as f32,
+                        (y * TILE_HEIGHT) as f32,
+                        WHITE,
+                    );
                 }
+                state.texture_atlas.draw(
+                    sprite,
+                    (x * TILE_WIDTH) as f32,
+                    (y * TILE_HEIGHT) as f32,
+                    WHITE,
+                );
             }
         }
     }
 }
 
+///Everything needed to draw one entity once it's been pulled out of the ECS
+struct DrawCall {
+    z_order: ZOrder,
+    pos: Vec2,
+    ///tint for the base sprite, layers keep their own colours
+    color: Color,
+    flip_x: bool,
+    ///an attack or hit flash playing over the whole entity, layers included
+    flash: Option<Flash>,
+    ///the base sprite followed by any layered on top of it
+    sprites: Vec<String>,
+}
+
+///Draws every renderable entity on the map, sorted by render layer and z order
 fn render_entities(state: &mut GameState) {
-    for (_id, (sprite_id, pos, ctrl_type)) in
-        state.ecs.query_mut::<(&Renderable, &IVec2, &ControlType)>()
+    let mut missing: Vec<String> = Vec::new();
+    //gather up everything that needs drawing first so it can be sorted into the right order
+    let mut draw_calls: Vec<DrawCall> = Vec::new();
+    for (_id, (sprite_id, pos, ctrl_type, z_order, visual_pos, animated, facing, flash)) in
+        state.ecs.query_mut::<(
+            &Renderable,
+            &IVec2,
+            Option<&ControlType>,
+            Option<&ZOrder>,
+            Option<&VisualPosition>,
+            Option<&AnimatedSprite>,
+            Option<&Facing>,
+            Option<&Flash>,
+        )>()
     {
-        let mut color = Color::new(0.0, 0.0, 0.0, 0.0);
-        match ctrl_type {
-            ControlType::PC => color = RED,
-            ControlType::NPC => color = BLUE,
+        let z_order = match (z_order, ctrl_type) {
+            (Some(z_order), _) => *z_order,
+            (None, Some(_)) => ZOrder::on(RenderLayer::Characters),
+            (None, None) => ZOrder::on(RenderLayer::Props),
+        };
+        let color = match ctrl_type {
+            Some(ctrl_type) => team_color(*ctrl_type),
+            None => WHITE,
+        };
+        //the base sprite and then each layer on top of it, all following the same animation
+        let base = sprite_id.get_sprite();
+        let sprites: Vec<String> = std::iter::once(&base)
+            .chain(sprite_id.get_layers().iter())
+            .map(|layer| frame_key(&state.texture_atlas, layer, animated))
+            .collect();
+        draw_calls.push(DrawCall {
+            z_order,
+            pos: draw_position(*pos, visual_pos),
+            color,
+            flip_x: facing == Some(&Facing::Left),
+            flash: flash.copied(),
+            sprites,
+        });
+    }
+    draw_calls.sort_by(|a, b| {
+        (a.z_order.layer, a.z_order.z)
+            .cmp(&(b.z_order.layer, b.z_order.z))
+            .then(a.pos.y.total_cmp(&b.pos.y))
+    });
+    for draw_call in draw_calls.iter() {
+        let params = DrawTextureParams {
+            flip_x: draw_call.flip_x,
+            ..Default::default()
+        };
+        //the base sprite is drawn in the team colour and the layers on top of it in their own colours
+        for (index, sprite) in draw_call.sprites.iter().enumerate() {
+            let color = if index == 0 { draw_call.color } else { WHITE };
+            let color = match draw_call.flash {
+                Some(flash) => flash.apply(color),
+                None => color,
+            };
+            if state.texture_atlas.report_missing(sprite) {
+                missing.push(sprite.clone());
+            }
+            state.texture_atlas.draw_ex(
+                sprite,
+                draw_call.pos.x,
+                draw_call.pos.y,
+                color,
+                params.clone(),
+            );
+        }
+    }
+    for sprite in missing.iter() {
+        state.ecs.spawn((DebugLogMessage::new(
+            DebugLevel::Error,
+            Subsystem::Render,
+            format!(
+                "no texture loaded for \"{}\", drawing a placeholder",
+                sprite
+            ),
+        ),));
+    }
+}
+
+///Works out which sprite to draw for a renderable, which is the current frame of its animation if it's animated
+///and the atlas has that animation for it, and otherwise just its sprite
+fn frame_key(atlas: &TextureAtlas, sprite: &str, animated: Option<&AnimatedSprite>) -> String {
+    if let Some(animated) = animated {
+        let key = animated.current().key(sprite);
+        if let Some(frame) = atlas.animation_frame(&key, animated.elapsed()) {
+            return frame.to_string();
+        }
+    }
+    sprite.to_string()
+}
+
+///Works out the pixel position to draw an entity at, following its visual position if it's sliding between tiles
+///and otherwise just drawing it on the tile it's logically on
+fn draw_position(pos: IVec2, visual_pos: Option<&VisualPosition>) -> Vec2 {
+    let tile = match visual_pos {
+        Some(visual_pos) => visual_pos.current(),
+        None => pos.as_vec2(),
+    };
+    Vec2::new(tile.x * TILE_WIDTH as f32, tile.y * TILE_HEIGHT as f32)
+}
+
+///the tint each side's characters are drawn with
+fn team_color(ctrl_type: ControlType) -> Color {
+    match ctrl_type {
+        ControlType::PC => RED,
+        ControlType::NPC => BLUE,
+    }
+}
+
+///Shades every tile someone on overwatch would react to, in their side's colour, so it's clear where it isn't safe
+///to walk
+fn render_overwatch(state: &mut GameState) {
+    let watchers: Vec<(Entity, Overwatch, Color)> = state
+        .ecs
+        .query::<(&Overwatch, &ControlType)>()
+        .iter()
+        .map(|(watcher, (overwatch, ctrl_type))| (watcher, *overwatch, team_color(*ctrl_type)))
+        .collect();
+    for (watcher, overwatch, color) in watchers {
+        for y in 0..MAP_HEIGHT {
+            for x in 0..MAP_WIDTH {
+                let tile = IVec2::new(x, y);
+                if can_watch_tile(state, watcher, &overwatch, tile) {
+                    draw_rectangle(
+                        (x * TILE_WIDTH) as f32,
+                        (y * TILE_HEIGHT) as f32,
+                        TILE_WIDTH as f32,
+                        TILE_HEIGHT as f32,
+                        Color::new(color.r, color.g, color.b, 0.15),
+                    );
+                }
+            }
         }
-        draw_texture(
-            state.texture_atlas.get(&sprite_id.get_sprite()).unwrap(),
+    }
+}
+
+///how many pixels the turn indicator arrow bobs up and down by
+const ARROW_BOB_HEIGHT: f32 = 4.0;
+///how fast the turn indicator arrow bobs, in radians per second
+const ARROW_BOB_SPEED: f32 = 5.0;
+
+///Draws the down arrow bobbing over whoever's turn it is so two identical looking characters can be told apart.
+///If they're on the top row of the map the arrow is flipped and drawn underneath them instead
+fn render_turn_indicator(state: &mut GameState, combat_encounter: &CombatEncounter) {
+    let Some(active_entity) = combat_encounter.next_turn() else {
+        return;
+    };
+    let Ok(pos) = state.ecs.get::<&IVec2>(active_entity).map(|pos| *pos) else {
+        return;
+    };
+    let visual_pos = state
+        .ecs
+        .get::<&VisualPosition>(active_entity)
+        .ok()
+        .map(|visual_pos| *visual_pos);
+    //follow the character while they're sliding between tiles
+    let draw_pos = draw_position(pos, visual_pos.as_ref());
+    let bob = (get_time() as f32 * ARROW_BOB_SPEED).sin() * ARROW_BOB_HEIGHT;
+    let (offset_y, flip_y) = if pos.y == 0 {
+        (TILE_HEIGHT as f32, true)
+    } else {
+        (-TILE_HEIGHT as f32, false)
+    };
+    state.texture_atlas.draw_ex(
+        "down arrow",
+        draw_pos.x,
+        draw_pos.y + offset_y + bob,
+        WHITE,
+        DrawTextureParams {
+            flip_y,
+            ..Default::default()
+        },
+    );
+}
+
+///returns the map tile the mouse is currently hovering over, if it's over the map at all
+pub fn mouse_tile(state: &GameState) -> Option<IVec2> {
+    let (mouse_x, mouse_y) = mouse_position();
+    let tile = IVec2::new(
+        (mouse_x / TILE_WIDTH as f32).floor() as i32,
+        (mouse_y / TILE_HEIGHT as f32).floor() as i32,
+    );
+    if state.map.in_bounds(tile) {
+        Some(tile)
+    } else {
+        None
+    }
+}
+
+///outlines a single map tile, used for pointing out the cursor and whatever's being targeted
+fn highlight_tile(tile: IVec2, color: Color) {
+    draw_rectangle_lines(
+        (tile.x * TILE_WIDTH) as f32,
+        (tile.y * TILE_HEIGHT) as f32,
+        TILE_WIDTH as f32,
+        TILE_HEIGHT as f32,
+        2.0,
+        color,
+    );
+}
+
+///Shades every tile covered by a lingering hazard
+fn render_hazards(state: &mut GameState) {
+    for (_id, (hazard, pos)) in state.ecs.query::<(&Hazard, &IVec2)>().iter() {
+        draw_rectangle(
             (pos.x * TILE_WIDTH) as f32,
             (pos.y * TILE_HEIGHT) as f32,
+            TILE_WIDTH as f32,
+            TILE_HEIGHT as f32,
+            hazard.kind.color(),
+        );
+    }
+}
+
+///While aiming an ability, shows the tiles it would reach from under the reticule. The reticule is green when it can
+///be used there and red when it can't
+fn render_ability_target(state: &mut GameState) {
+    if !state.control_state.is(CombatActionType::Ability) {
+        return;
+    }
+    let Some((reticule, reticule_pos)) = find_reticule(&state.ecs) else {
+        return;
+    };
+    let Ok((owner, slot)) = state
+        .ecs
+        .query_one_mut::<(&Reticule, &AimingAbility)>(reticule)
+        .map(|(reticule, aiming)| (reticule.get_owner(), aiming.0))
+    else {
+        return;
+    };
+    let Some(ability) = ability_in_slot(state, owner, slot) else {
+        return;
+    };
+    let valid = target_problem(state, owner, &ability, reticule_pos).is_none();
+    if valid {
+        for tile in ability_tiles(state, owner, &ability, reticule_pos) {
+            draw_rectangle(
+                (tile.x * TILE_WIDTH) as f32,
+                (tile.y * TILE_HEIGHT) as f32,
+                TILE_WIDTH as f32,
+                TILE_HEIGHT as f32,
+                Color::new(0.3, 0.6, 1.0, 0.25),
+            );
+        }
+    }
+    highlight_tile(reticule_pos, if valid { GREEN } else { RED });
+}
+
+///Draws the active combatant's abilities at the bottom of the side panel, each with the number key that uses it,
+///what it costs and whether it's ready. Returns where the bar starts so the log can stop short of it
+fn render_action_bar(state: &mut GameState, combat_encounter: &CombatEncounter) -> f32 {
+    let Some(active_entity) = combat_encounter.next_turn() else {
+        return screen_height();
+    };
+    let bar = action_bar(state, active_entity);
+    if bar.is_empty() {
+        return screen_height();
+    }
+    let panel_x = (MAP_WIDTH * TILE_WIDTH) as f32;
+    let panel_width = screen_width() - panel_x;
+    let top = screen_height() - LOG_LINE_HEIGHT * (bar.len() as f32 + 1.0) - LOG_PADDING * 2.0;
+    draw_rectangle(
+        panel_x,
+        top,
+        panel_width,
+        screen_height() - top,
+        Color::new(0.12, 0.12, 0.15, 1.0),
+    );
+    draw_text(
+        "Abilities",
+        panel_x + LOG_PADDING,
+        top + LOG_LINE_HEIGHT,
+        LOG_FONT_SIZE,
+        WHITE,
+    );
+    for (i, (slot, ability, cooldown)) in bar.iter().enumerate() {
+        let cost = match ability.cost.kind {
+            ActionKind::Free => String::from("free"),
+            ActionKind::Bonus => format!("{} AP bonus", ability.cost.ap),
+            ActionKind::Standard => format!("{} AP", ability.cost.ap),
+        };
+        let status = if *cooldown > 0 {
+            format!("{} rounds", cooldown)
+        } else {
+            String::from("ready")
+        };
+        let line = format!(
+            "[{}] {}  {}  {}  {}",
+            slot + 1,
+            ability.name,
+            cost,
+            ability.targeting.label(),
+            status
+        );
+        draw_text(
+            &line,
+            panel_x + LOG_PADDING,
+            top + LOG_LINE_HEIGHT * (i as f32 + 2.0),
+            LOG_FONT_SIZE,
+            if *cooldown > 0 { GRAY } else { LIGHTGRAY },
+        );
+    }
+    top
+}
+
+///Marks every tile with an item lying on it
+fn render_items(state: &mut GameState) {
+    for (_id, (_item, pos)) in state.ecs.query::<(&Item, &IVec2)>().iter() {
+        let size = TILE_WIDTH as f32 / 3.0;
+        draw_rectangle(
+            (pos.x * TILE_WIDTH) as f32 + (TILE_WIDTH as f32 - size) / 2.0,
+            (pos.y * TILE_HEIGHT) as f32 + (TILE_HEIGHT as f32 - size) / 2.0,
+            size,
+            size,
+            GOLD,
+        );
+    }
+}
+
+const GEAR_PANEL_WIDTH: f32 = 420.0;
+
+///While the gear panel is open, lists what the active entity has in each slot followed by everything they're
+///carrying, with the line that's highlighted marked
+fn render_gear_panel(state: &mut GameState, combat_encounter: &CombatEncounter) {
+    if !state.control_state.is(CombatActionType::Inventory) {
+        return;
+    }
+    let Some(active_entity) = combat_encounter.next_turn() else {
+        return;
+    };
+    let gear = gear_list(&state.ecs, active_entity);
+    let height = LOG_LINE_HEIGHT * (gear.len() as f32 + 2.0) + LOG_PADDING;
+    let x = ((MAP_WIDTH * TILE_WIDTH) as f32 - GEAR_PANEL_WIDTH) / 2.0;
+    let y = ((MAP_HEIGHT * TILE_HEIGHT) as f32 - height) / 2.0;
+    draw_rectangle(
+        x,
+        y,
+        GEAR_PANEL_WIDTH,
+        height,
+        Color::new(0.0, 0.0, 0.0, 0.85),
+    );
+    draw_rectangle_lines(x, y, GEAR_PANEL_WIDTH, height, 2.0, GRAY);
+    let ready = state
+        .ecs
+        .get::<&Equipment>(active_entity)
+        .map(|equipment| equipment.ready())
+        .ok();
+    draw_text(
+        &format!("{}'s gear", entity_name(&state.ecs, active_entity)),
+        x + LOG_PADDING,
+        y + LOG_LINE_HEIGHT,
+        LOG_FONT_SIZE,
+        WHITE,
+    );
+    for (i, entry) in gear.iter().enumerate() {
+        let line_y = y + LOG_LINE_HEIGHT * (i as f32 + 2.0);
+        let slot = match entry.slot {
+            //the weapon slot that's in hand is marked so it's clear what a swap will do
+            Some(slot) if Some(slot) == ready => format!("{}*", slot.label()),
+            Some(slot) => slot.label().to_string(),
+            None => String::from("Carried"),
+        };
+        let item = match entry.item {
+            Some(item) => entity_name(&state.ecs, item),
+            None => String::from("-"),
+        };
+        let highlighted = i == state.gear_cursor;
+        let color = if highlighted { YELLOW } else { LIGHTGRAY };
+        let marker = if highlighted { ">" } else { " " };
+        draw_text(marker, x + LOG_PADDING, line_y, LOG_FONT_SIZE, color);
+        draw_text(&slot, x + LOG_PADDING * 3.0, line_y, LOG_FONT_SIZE, color);
+        draw_text(&item, x + 140.0, line_y, LOG_FONT_SIZE, color);
+    }
+}
+
+///While picking where to throw, shows the tiles the blast would reach if it landed under the reticule and the chance
+///of it landing there. The reticule is green when the throw can be made and red when it can't
+fn render_throw(state: &mut GameState) {
+    if !state.control_state.is(CombatActionType::Throwing) {
+        return;
+    }
+    let Some((reticule, reticule_pos)) = find_reticule(&state.ecs) else {
+        return;
+    };
+    let Ok(owner) = state
+        .ecs
+        .get::<&Reticule>(reticule)
+        .map(|reticule| reticule.get_owner())
+    else {
+        return;
+    };
+    let (Ok(pos), Some((_item, throwable))) = (
+        state.ecs.get::<&IVec2>(owner).map(|pos| *pos),
+        equipped_throwable(&state.ecs, owner),
+    ) else {
+        return;
+    };
+    let distance = crate::lib::geometry::distance(pos, reticule_pos);
+    let valid =
+        distance <= throwable.range && landing_tile(state, pos, reticule_pos) == reticule_pos;
+    if valid {
+        for tile in blast_tiles(state, throwable.template, pos, reticule_pos) {
+            draw_rectangle(
+                (tile.x * TILE_WIDTH) as f32,
+                (tile.y * TILE_HEIGHT) as f32,
+                TILE_WIDTH as f32,
+                TILE_HEIGHT as f32,
+                Color::new(1.0, 0.3, 0.0, 0.25),
+            );
+        }
+    }
+    highlight_tile(reticule_pos, if valid { GREEN } else { RED });
+    let label = format!(
+        "{}/{}  {}%",
+        distance,
+        throwable.range,
+        throw_chance(&throwable, distance)
+    );
+    draw_text(
+        &label,
+        (reticule_pos.x * TILE_WIDTH) as f32,
+        (reticule_pos.y * TILE_HEIGHT) as f32 - 4.0,
+        HUD_FONT_SIZE * 0.75,
+        WHITE,
+    );
+}
+
+///While picking who to hit in melee, marks every neighbouring tile with someone standing on it
+fn render_melee_targets(state: &mut GameState, combat_encounter: &CombatEncounter) {
+    if !state.control_state.is(CombatActionType::Melee) {
+        return;
+    }
+    let Some(active_entity) = combat_encounter.next_turn() else {
+        return;
+    };
+    let Ok(pos) = state.ecs.get::<&IVec2>(active_entity).map(|pos| *pos) else {
+        return;
+    };
+    for y in -1..=1 {
+        for x in -1..=1 {
+            let tile = pos + IVec2::new(x, y);
+            if tile != pos && combatant_at(state, tile).is_some() {
+                highlight_tile(tile, RED);
+            }
+        }
+    }
+}
+
+///Draws the targeting reticule along with the line a shot would take to reach it. The reticule is green when the shot
+///can be taken, yellow when it's out of range and red when something's in the way or there's nobody to shoot
+fn render_targeting(state: &mut GameState) {
+    if !state.control_state.is(CombatActionType::Targeting) {
+        return;
+    }
+    let Some((reticule, reticule_pos)) = find_reticule(&state.ecs) else {
+        return;
+    };
+    let Ok(owner) = state
+        .ecs
+        .get::<&Reticule>(reticule)
+        .map(|reticule| reticule.get_owner())
+    else {
+        return;
+    };
+    let Some(info) = targeting_info(state, owner, reticule_pos) else {
+        return;
+    };
+    let color = if info.is_valid() {
+        GREEN
+    } else if info.target.is_some() && !info.in_range() {
+        YELLOW
+    } else {
+        RED
+    };
+    //dots along the line of fire, turning red from wherever it's blocked
+    let mut blocked = false;
+    for tile in info.path.iter() {
+        if Some(*tile) == info.blocked_at {
+            blocked = true;
+        }
+        let dot_color = if blocked { RED } else { WHITE };
+        draw_circle(
+            (tile.x * TILE_WIDTH) as f32 + TILE_WIDTH as f32 / 2.0,
+            (tile.y * TILE_HEIGHT) as f32 + TILE_HEIGHT as f32 / 2.0,
+            2.0,
+            dot_color,
+        );
+    }
+    //a crosshair over the reticule's tile
+    let center = Vec2::new(
+        (reticule_pos.x * TILE_WIDTH) as f32 + TILE_WIDTH as f32 / 2.0,
+        (reticule_pos.y * TILE_HEIGHT) as f32 + TILE_HEIGHT as f32 / 2.0,
+    );
+    highlight_tile(reticule_pos, color);
+    draw_line(
+        center.x - 10.0,
+        center.y,
+        center.x + 10.0,
+        center.y,
+        2.0,
+        color,
+    );
+    draw_line(
+        center.x,
+        center.y - 10.0,
+        center.x,
+        center.y + 10.0,
+        2.0,
+        color,
+    );
+    draw_text(
+        &format!("{}/{}", info.distance, info.range),
+        (reticule_pos.x * TILE_WIDTH) as f32,
+        ((reticule_pos.y + 1) * TILE_HEIGHT) as f32 + 12.0,
+        16.0,
+        color,
+    );
+    //hovering the reticule over someone shows exactly how the chance to hit them adds up
+    if let Some(inputs) = info
+        .target
+        .and_then(|target| gather_inputs(state, owner, target, AttackKind::Ranged))
+    {
+        render_hit_breakdown(&hit_chance(&inputs), reticule_pos);
+    }
+}
+
+///Draws the itemised chance to hit in a box next to the tile being aimed at, flipping to the other side of it
+///if it would run off the edge of the map
+fn render_hit_breakdown(chance: &HitChance, tile: IVec2) {
+    let font_size = 16.0;
+    let line_height = 18.0;
+    let width = 220.0;
+    let height = line_height * (chance.breakdown.len() as f32 + 1.0) + 8.0;
+    let mut x = ((tile.x + 1) * TILE_WIDTH) as f32 + 8.0;
+    if x + width > (MAP_WIDTH * TILE_WIDTH) as f32 {
+        x = (tile.x * TILE_WIDTH) as f32 - width - 8.0;
+    }
+    let y = ((tile.y * TILE_HEIGHT) as f32).min((MAP_HEIGHT * TILE_HEIGHT) as f32 - height);
+    draw_rectangle(x, y, width, height, Color::new(0.0, 0.0, 0.0, 0.8));
+    let mut line_y = y + line_height;
+    for (reason, amount) in chance.breakdown.iter() {
+        draw_text(reason, x + 6.0, line_y, font_size, LIGHTGRAY);
+        draw_text(
+            &format!("{:+}", amount),
+            x + width - 50.0,
+            line_y,
+            font_size,
+            if *amount < 0 { RED } else { GREEN },
+        );
+        line_y += line_height;
+    }
+    draw_text("Chance to hit", x + 6.0, line_y, font_size, WHITE);
+    draw_text(
+        &format!("{}%", chance.probability),
+        x + width - 50.0,
+        line_y,
+        font_size,
+        WHITE,
+    );
+}
+
+///highlights the tile under the mouse cursor
+fn render_cursor(state: &mut GameState) {
+    if let Some(tile) = mouse_tile(state) {
+        highlight_tile(tile, YELLOW);
+    }
+}
+
+const HUD_FONT_SIZE: f32 = 20.0;
+
+///Draws the strip under the map describing who's turn it is, what they have left to spend and what the player can do
+fn render_hud(state: &mut GameState, combat_encounter: &CombatEncounter) {
+    let Some(active_entity) = combat_encounter.next_turn() else {
+        return;
+    };
+    let hud_y = (MAP_HEIGHT * TILE_HEIGHT) as f32;
+    let actor = entity_name(&state.ecs, active_entity);
+    let description = match state.ecs.get::<&Name>(active_entity) {
+        Ok(name) => name.get_description(),
+        Err(_) => String::new(),
+    };
+    let ap = match state.ecs.get::<&ActionPoints>(active_entity) {
+        Ok(ap) if ap.bonus() > 0 => format!("{}/{} +{} bonus", ap.get(), ap.max(), ap.bonus()),
+        Ok(ap) => format!("{}/{}", ap.get(), ap.max()),
+        Err(_) => String::from("-"),
+    };
+    let mp = match state.ecs.get::<&MovementPoints>(active_entity) {
+        Ok(mp) => mp.current().to_string(),
+        Err(_) => String::from("-"),
+    };
+    let hp = match state.ecs.get::<&Health>(active_entity) {
+        Ok(health) => format!("{}/{}", health.current(), health.max()),
+        Err(_) => String::from("-"),
+    };
+    let ammo = match equipped_magazine(&state.ecs, active_entity) {
+        Some(magazine) => {
+            let mut ammo = format!(
+                "{}/{} +{} {}",
+                magazine.loaded(),
+                magazine.capacity(),
+                magazine.spare(),
+                selected_fire_mode(&state.ecs, active_entity).label()
+            );
+            //let the player know before they try to fire and hear the click
+            if ammo_problem(&state.ecs, active_entity).is_some() && magazine.can_reload() {
+                ammo.push_str(" (reload!)");
+            }
+            ammo
+        }
+        None => String::from("-"),
+    };
+    let status = format_message(
+        "Round {round} - {actor}'s turn ({doing}) | HP {hp} | AP {ap} | MP {mp} | Ammo {ammo} | {description}",
+        &[
+            ("round", combat_encounter.get_round().to_string()),
+            ("actor", actor.clone()),
+            ("doing", state.control_state.current().label().to_string()),
+            ("hp", hp),
+            ("ap", ap),
+            ("mp", mp),
+            ("ammo", ammo),
+            ("description", description),
+        ],
+    );
+    let prompt = match state.control_state.current() {
+        CombatActionType::None => {
+            String::from("[S] move   [F] aim   [A] melee   [G] throw   [1-9] abilities   [R] reload   [Tab] fire mode   [X] swap   [P] pick up   [I] gear   [O] overwatch   [U] undo move   [Enter] end turn   [Shift+Q] quit")
+        }
+        CombatActionType::Ability => {
+            String::from("Arrows/numpad to aim   [Enter] use ability   [Esc] cancel")
+        }
+        CombatActionType::Inventory => String::from(
+            "Up/Down to pick   [Enter] equip/unequip   [D] drop   [Esc] close",
+        ),
+        CombatActionType::Targeting => {
+            String::from("Arrows/numpad to aim   [Enter] confirm target   [Tab] fire mode   [O] overwatch this way   [Esc] cancel")
+        }
+        CombatActionType::Throwing => {
+            String::from("Arrows/numpad to aim   [Enter] throw   [Esc] cancel")
+        }
+        CombatActionType::Melee => {
+            String::from("Arrows/numpad to pick who to hit   [Esc] cancel")
+        }
+        CombatActionType::Movement => {
+            String::from("Arrows/numpad to step   [U] undo step   [Esc] stop moving")
+        }
+        CombatActionType::EndTurn => {
+            format_message("End {actor}'s turn? [Y]/[N]", &[("actor", actor)])
+        }
+    };
+    //while a replay is playing the player can only drive the playback, so that's what gets prompted
+    let prompt = match &state.playback {
+        Some(playback) => {
+            let (played, total) = playback.progress();
+            let mode = if playback.is_finished() {
+                "finished"
+            } else if playback.is_paused() {
+                "paused"
+            } else if playback.is_fast_forward() {
+                "fast forward"
+            } else {
+                "playing"
+            };
+            format!(
+                "Replay {}/{} ({})   [Space] pause   [Right] step   [F] fast forward   [Shift+Q] quit",
+                played, total, mode
+            )
+        }
+        None => prompt,
+    };
+    draw_text(&status, 8.0, hud_y + HUD_FONT_SIZE, HUD_FONT_SIZE, WHITE);
+    draw_text(
+        &prompt,
+        8.0,
+        hud_y + HUD_FONT_SIZE * 2.2,
+        HUD_FONT_SIZE,
+        LIGHTGRAY,
+    );
+}
+
+const LOG_FONT_SIZE: f32 = 18.0;
+const LOG_LINE_HEIGHT: f32 = 20.0;
+const LOG_PADDING: f32 = 8.0;
+
+///Draws the initiative order at the top of the side panel, with the active combatant marked and anyone who's already
+///gone this round greyed out. Returns how far down the panel it reaches so the log can be drawn underneath it
+fn render_initiative_strip(state: &mut GameState, combat_encounter: &CombatEncounter) -> f32 {
+    let panel_x = (MAP_WIDTH * TILE_WIDTH) as f32;
+    let panel_width = screen_width() - panel_x;
+    draw_rectangle(
+        panel_x,
+        0.0,
+        panel_width,
+        screen_height(),
+        Color::new(0.08, 0.08, 0.1, 1.0),
+    );
+    draw_text(
+        "Initiative",
+        panel_x + LOG_PADDING,
+        LOG_LINE_HEIGHT,
+        LOG_FONT_SIZE,
+        WHITE,
+    );
+    let active_entity = combat_encounter.next_turn();
+    let mut y = LOG_LINE_HEIGHT * 2.0;
+    for entity in combat_encounter.get_all_entities() {
+        let marker = if Some(entity) == active_entity {
+            ">"
+        } else {
+            " "
+        };
+        let color = if combat_encounter.has_completed_turn(entity) {
+            GRAY
+        } else {
+            match state.ecs.get::<&ControlType>(entity) {
+                Ok(ctrl_type) => team_color(*ctrl_type),
+                Err(_) => WHITE,
+            }
+        };
+        draw_text(
+            &format!("{} {}", marker, entity_name(&state.ecs, entity)),
+            panel_x + LOG_PADDING,
+            y,
+            LOG_FONT_SIZE,
             color,
         );
+        y += LOG_LINE_HEIGHT;
+    }
+    y
+}
+
+///Draws the game log in the side panel underneath the initiative strip, newest messages at the bottom.
+///Messages too long for the panel are wrapped onto multiple lines
+fn render_log_panel(state: &mut GameState, panel_top: f32, panel_bottom: f32) {
+    let panel_x = (MAP_WIDTH * TILE_WIDTH) as f32;
+    let panel_width = screen_width() - panel_x;
+    let panel_height = panel_bottom;
+    draw_text(
+        "Combat Log (F5 to export)",
+        panel_x + LOG_PADDING,
+        panel_top + LOG_LINE_HEIGHT,
+        LOG_FONT_SIZE,
+        WHITE,
+    );
+    if state.log.scroll() > 0 {
+        draw_text(
+            &format!("(scrolled back {}, End to return)", state.log.scroll()),
+            panel_x + LOG_PADDING,
+            panel_top + LOG_LINE_HEIGHT * 2.0,
+            LOG_FONT_SIZE,
+            GRAY,
+        );
+    }
+    let top = panel_top + LOG_LINE_HEIGHT * 3.0;
+    let text_width = panel_width - LOG_PADDING * 2.0;
+    //work up from the bottom of the panel until it's full
+    let mut y = panel_height - LOG_PADDING;
+    let max_entries = ((panel_height - top) / LOG_LINE_HEIGHT) as usize;
+    for entry in state.log.visible(max_entries).rev() {
+        let line = format!("[{}] {}", entry.stamp(), entry.text);
+        let wrapped = wrap_text(&line, text_width);
+        for wrapped_line in wrapped.iter().rev() {
+            if y < top {
+                return;
+            }
+            draw_text(
+                wrapped_line,
+                panel_x + LOG_PADDING,
+                y,
+                LOG_FONT_SIZE,
+                entry.category.color(),
+            );
+            y -= LOG_LINE_HEIGHT;
+        }
+    }
+}
+
+///splits a string into lines that fit within the given width at the log font size, breaking on spaces
+fn wrap_text(text: &str, max_width: f32) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if current.is_empty() {
+            word.to_string()
+        } else {
+            format!("{} {}", current, word)
+        };
+        if measure_text(&candidate, None, LOG_FONT_SIZE as u16, 1.0).width > max_width
+            && !current.is_empty()
+        {
+            lines.push(current);
+            current = word.to_string();
+        } else {
+            current = candidate;
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
     }
+    lines
 }
@@ -0,0 +1,28 @@
+use crate::prelude::*;
+use hecs::*;
+
+///Game system that plays every sound effect requested this frame
+pub fn system(state: &mut GameState) {
+    let mut cmd_buf = CommandBuffer::new();
+    let mut missing: Vec<String> = Vec::new();
+    for (id, effect) in state.ecs.query_mut::<&SoundEffect>() {
+        if !state
+            .audio
+            .play(&state.sound_atlas, effect.category, &effect.sound)
+        {
+            missing.push(effect.sound.clone());
+        }
+        cmd_buf.despawn(id);
+    }
+    cmd_buf.run_on(&mut state.ecs);
+    //a missing sound is usually a key that's not in the atlas, which is worth hearing about but only the once
+    for sound in missing.iter() {
+        if state.audio.note_missing(sound) {
+            state.ecs.spawn((DebugLogMessage::new(
+                DebugLevel::Warn,
+                Subsystem::Audio,
+                format!("no sound loaded for \"{}\"", sound),
+            ),));
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use crate::audio::sounds;
+use crate::lib::blast::*;
+use crate::lib::format::*;
+use crate::lib::geometry::*;
+use crate::lib::inventory::*;
+use crate::lib::skills::*;
+use crate::lib::targeting::*;
+use crate::prelude::*;
+use hecs::*;
+use macroquad::prelude::*;
+
+///Game system that resolves every throw MOI, working out where the throw lands and setting it off
+pub fn system(state: &mut GameState, combat_encounter: &mut CombatEncounter) {
+    let mut throws: Vec<MOIThrow> = Vec::new();
+    let mut cmd_buf = CommandBuffer::new();
+    for (id, moi) in state.ecs.query_mut::<&MOIThrow>() {
+        throws.push(*moi);
+        cmd_buf.despawn(id);
+    }
+    cmd_buf.run_on(&mut state.ecs);
+    for throw in throws.iter() {
+        let (thrower, target) = throw.get();
+        resolve_throw(state, combat_encounter, thrower, target);
+    }
+}
+
+///Throws the thrower's equipped throwable at a tile. A failed throw scatters somewhere around the tile it was aimed
+///at, then the blast goes off wherever it lands
+fn resolve_throw(
+    state: &mut GameState,
+    combat_encounter: &mut CombatEncounter,
+    thrower: Entity,
+    target: IVec2,
+) {
+    let actor = entity_name(&state.ecs, thrower);
+    let (Some((item, throwable)), Ok(thrower_pos)) = (
+        equipped_throwable(&state.ecs, thrower),
+        state.ecs.get::<&IVec2>(thrower).map(|pos| *pos),
+    ) else {
+        state.ecs.spawn((DebugLogMessage::new(
+            DebugLevel::Warn,
+            Subsystem::Combat,
+            format_message(
+                "{actor} tried to throw something but isn't on the map or has nothing to throw",
+                &[("actor", actor)],
+            ),
+        ),));
+        return;
+    };
+    let item_name = entity_name(&state.ecs, item);
+    let thrown = distance(thrower_pos, target);
+    //an untrained thrower with average agility lands it exactly as often as the throw chance says
+    let check = skill_check(
+        state,
+        thrower,
+        Skill::Athletics,
+        throw_chance(&throwable, thrown) - BASE_SKILL_TARGET,
+    );
+    state.ecs.spawn((GameLogMessage::new(
+        LogCategory::Combat,
+        format_message(
+            "{actor} throws the {item} at {tile}, needing under {target} on {skill} and rolling {roll} for {outcome}.",
+            &[
+                ("actor", actor.clone()),
+                ("item", item_name.clone()),
+                ("tile", tile_name(target)),
+                ("target", check.target.to_string()),
+                ("skill", check.skill.label().to_string()),
+                ("roll", check.roll.to_string()),
+                ("outcome", check.describe().to_string()),
+            ],
+        ),
+    ),));
+    let aimed_at = if check.succeeded() {
+        target
+    } else {
+        //a near miss only drifts a tile, a bad one can end up as far as the throw allows
+        let spread = scatter_distance(thrown).min(-check.degrees);
+        let offset = IVec2::new(
+            state.rng.range(RngStream::Scatter, -spread, spread),
+            state.rng.range(RngStream::Scatter, -spread, spread),
+        );
+        //a miss always ends up somewhere other than where it was aimed
+        if offset == IVec2::ZERO {
+            target + IVec2::new(spread, 0)
+        } else {
+            target + offset
+        }
+    };
+    let centre = landing_tile(state, thrower_pos, aimed_at);
+    if centre != target {
+        state.ecs.spawn((GameLogMessage::new(
+            LogCategory::Combat,
+            format_message(
+                "The {item} goes wide and lands at {tile}.",
+                &[("item", item_name.clone()), ("tile", tile_name(centre))],
+            ),
+        ),));
+    }
+    //throwing it uses it up
+    remove_item(&mut state.ecs, thrower, item);
+    crate::lib::systems::despawn_entity(state, item);
+    let _ = state.ecs.insert_one(thrower, Flash::attack());
+    if let Ok(animated) = state.ecs.query_one_mut::<&mut AnimatedSprite>(thrower) {
+        animated.play(AnimationName::Shoot);
+    }
+    state
+        .ecs
+        .spawn((SoundEffect::new(SoundCategory::Combat, sounds::EXPLOSION),));
+    explode(state, combat_encounter, &throwable, thrower_pos, centre);
+}
+
+///Sets off a blast at a tile, hurting everyone it reaches, knocking down cover if it's strong enough and leaving
+///behind whatever lingers after it
+fn explode(
+    state: &mut GameState,
+    combat_encounter: &mut CombatEncounter,
+    throwable: &Throwable,
+    origin: IVec2,
+    centre: IVec2,
+) {
+    let tiles = blast_tiles(state, throwable.template, origin, centre);
+    //everyone caught in the same blast is hit by the same roll, it's only distance and cover that set them apart
+    let rolled = state.rng.roll(RngStream::Damage, &throwable.damage);
+    //things like smoke don't hurt anyone, they only leave something behind
+    let harmless = throwable.damage.max() <= 0;
+    let victims: Vec<(Entity, IVec2)> = if harmless {
+        Vec::new()
+    } else {
+        combatants_in(state, &tiles)
+    };
+    for (victim, pos) in victims {
+        //works out cover before any low walls get knocked flat
+        let (damage, cover) = blast_damage(state, throwable, centre, pos, rolled);
+        if damage > 0 {
+            super::attack::apply_damage(state, combat_encounter, victim, damage);
+        } else {
+            let reason = if cover == CoverLevel::None {
+                String::from("is far enough away")
+            } else {
+                format!("is shielded by {}", cover.label().to_lowercase())
+            };
+            state.ecs.spawn((GameLogMessage::new(
+                LogCategory::Combat,
+                format_message(
+                    "{target} is caught in the blast but {reason}.",
+                    &[
+                        ("target", entity_name(&state.ecs, victim)),
+                        ("reason", reason),
+                    ],
+                ),
+            ),));
+        }
+    }
+    if throwable.destroys_cover {
+        for tile in tiles.iter() {
+            if state.map.destroy_cover(*tile) {
+                state.ecs.spawn((GameLogMessage::new(
+                    LogCategory::Combat,
+                    format_message(
+                        "The blast knocks down the low wall at {tile}.",
+                        &[("tile", tile_name(*tile))],
+                    ),
+                ),));
+            }
+        }
+    }
+    if let Some((kind, rounds)) = throwable.lingering {
+        super::hazards::spread(state, kind, rounds, &tiles);
+        state.ecs.spawn((GameLogMessage::new(
+            LogCategory::Combat,
+            format_message(
+                "The blast leaves {hazard} around {tile}.",
+                &[
+                    ("hazard", kind.label().to_string()),
+                    ("tile", tile_name(centre)),
+                ],
+            ),
+        ),));
+    }
+}
@@ -1,31 +1,162 @@
 mod moi;
 pub use moi::*;
 
+use crate::audio::SoundCategory;
+use crate::debug_log::{DebugLevel, Subsystem};
+use crate::dice::Dice;
+use crate::game_log::LogCategory;
 use hecs::*;
 use macroquad::prelude::*;
+use std::collections::HashMap;
 //this is the big mod file for components that will expand as I add more components
 
 ///Component that allows an entity to be rendered, contains the hashmap key needed to retrieve
-///the necessary Texture2D from the texture atlas
+///the necessary Texture2D from the texture atlas, along with any extra sprites layered on top of it (eg. equipment)
 #[derive(Clone, Debug)]
 pub struct Renderable {
     sprite: String,
+    layers: Vec<String>,
 }
 impl Renderable {
     pub fn new(sprite: String) -> Self {
-        Self { sprite }
+        Self {
+            sprite,
+            layers: Vec::new(),
+        }
+    }
+    ///creates a renderable with sprites drawn on top of the base one, in order
+    pub fn with_layers(sprite: String, layers: Vec<String>) -> Self {
+        Self { sprite, layers }
     }
     pub fn get_sprite(&self) -> String {
         self.sprite.clone()
     }
+    pub fn get_layers(&self) -> &Vec<String> {
+        &self.layers
+    }
+}
+
+///The layers entities are drawn in, from the bottom up. Everything on one layer is drawn over everything on the
+///layers before it no matter what order the ECS hands them over in. The map itself is always drawn underneath all of
+///them, new layers slot in wherever they need to be drawn
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum RenderLayer {
+    Props,
+    Characters,
+    UiOverlay,
+}
+
+///Component deciding where an entity is drawn in relation to everything else. Within a layer higher z values are
+///drawn on top, and anything with the same z is drawn top of the map first so things lower down overlap them.
+///Entities without one are drawn on the characters layer if they're controlled by someone and the props layer if not
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ZOrder {
+    pub layer: RenderLayer,
+    pub z: i32,
+}
+impl ZOrder {
+    ///puts an entity on a layer at the default z
+    pub fn on(layer: RenderLayer) -> Self {
+        Self { layer, z: 0 }
+    }
+}
+
+///The animations every character can have, looked up in the texture atlas as "<sprite> <name>"
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AnimationName {
+    Idle,
+    Walk,
+    Shoot,
+    Die,
+}
+impl AnimationName {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AnimationName::Idle => "idle",
+            AnimationName::Walk => "walk",
+            AnimationName::Shoot => "shoot",
+            AnimationName::Die => "die",
+        }
+    }
+    ///the texture atlas key for this animation of a sprite, eg. "character walk"
+    pub fn key(&self, sprite: &str) -> String {
+        format!("{} {}", sprite, self.label())
+    }
+}
+
+///Component that plays named animations for a Renderable instead of always drawing the same sprite.
+///Layers on the renderable play the same animation if they have one
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct AnimatedSprite {
+    current: AnimationName,
+    elapsed: f32,
+}
+impl AnimatedSprite {
+    pub fn new() -> Self {
+        Self {
+            current: AnimationName::Idle,
+            elapsed: 0.0,
+        }
+    }
+    ///switches to a different animation, starting it from the beginning. Asking for the one that's
+    ///already playing carries on with it
+    pub fn play(&mut self, animation: AnimationName) {
+        if self.current != animation {
+            self.current = animation;
+            self.elapsed = 0.0;
+        }
+    }
+    pub fn update(&mut self, delta_time: f32) {
+        self.elapsed += delta_time;
+    }
+    pub fn current(&self) -> AnimationName {
+        self.current
+    }
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+}
+
+///Component for which way an entity is facing, sprites are drawn facing right and flipped to face left
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Facing {
+    Left,
+    Right,
+}
+impl Facing {
+    ///works out which way something is facing after moving by a delta, vertical moves keep the old facing
+    pub fn from_delta(delta: IVec2, previous: Facing) -> Self {
+        match delta.x {
+            x if x < 0 => Facing::Left,
+            x if x > 0 => Facing::Right,
+            _ => previous,
+        }
+    }
+}
+///Component that gives an entity an identity that can be shown to the player in the game log and HUD
+#[derive(Clone, Debug)]
+pub struct Name {
+    name: String,
+    description: String,
+}
+impl Name {
+    pub fn new(name: String, description: String) -> Self {
+        Self { name, description }
+    }
+    pub fn get_name(&self) -> String {
+        self.name.clone()
+    }
+    pub fn get_description(&self) -> String {
+        self.description.clone()
+    }
 }
 ///Newtype wrapper around a 2D vector integer used for tracking the location of tile-map entities on the screen
 ///(this will be basically everything except for effects)
 // pub struct GridPosition(IVec2);
-
 ///This enum will be used in this sandbox simply for determining which team someone is on but in the future
 ///will be used for determining who in combat is controlled by the player and who by AI
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum ControlType {
     PC,  //for hotseat mode synonymous w/ player 1
     NPC, //for hotseat mode synonymous w/ player 2
@@ -63,7 +194,7 @@ impl CombatEncounter {
                 return Some(*entity);
             }
         }
-        return None;
+        None
     }
     ///Used to mark a character's turn in the initiative order as complete. By default done whenever a character uses up
     ///all of their Action Points. Returns a bool that expresses whether the turn being completed also completes a full round
@@ -71,7 +202,7 @@ impl CombatEncounter {
     ///(eg. poison gas that lingers for 3 rounds)
     pub fn complete_turn(&mut self) -> bool {
         //iterate through the initiative order
-        for (entity, completed_turn) in self.initiative_order.iter_mut() {
+        for (_entity, completed_turn) in self.initiative_order.iter_mut() {
             //when you find the first one that hasn't completed their turn mark it as complete
             if !*completed_turn {
                 *completed_turn = true;
@@ -103,63 +234,953 @@ impl CombatEncounter {
             for (_entity, completed_turn) in self.initiative_order.iter_mut() {
                 *completed_turn = false;
             }
-            return true;
-        } else {
-            return false;
         }
+        is_complete
+    }
+    ///checks if an entity has already taken their turn this round
+    pub fn has_completed_turn(&self, entity: Entity) -> bool {
+        self.initiative_order
+            .iter()
+            .any(|(id, completed_turn)| *id == entity && *completed_turn)
+    }
+    ///returns the round the encounter is currently on, counting from 1
+    pub fn get_round(&self) -> i32 {
+        self.num_rounds + 1
+    }
+    ///Takes an entity out of the initiative order, eg. when they go down. Returns true if they were in it
+    pub fn remove_combatant(&mut self, entity: Entity) -> bool {
+        let before = self.initiative_order.len();
+        self.initiative_order
+            .retain(|(id, _completed_turn)| *id != entity);
+        before != self.initiative_order.len()
     }
     ///returns all of the entities in the combat encounter in initiative order.
     pub fn get_all_entities(&self) -> Vec<Entity> {
         let mut all_entities: Vec<Entity> = Vec::new();
         for (entity_id, _has_completed) in self.initiative_order.iter() {
-            all_entities.push(entity_id.clone());
+            all_entities.push(*entity_id);
         }
         all_entities
     }
 }
 
-///An entity's action points used for the turn based combat system
+///Whether an action comes out of the AP pool, uses up a bonus action or costs nothing at all
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ActionKind {
+    Standard,
+    ///uses up one of the bonus actions for the round if there are any left, otherwise it costs AP like anything else
+    Bonus,
+    Free,
+}
+
+///What an action costs to take
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ActionCost {
+    pub ap: i32,
+    pub kind: ActionKind,
+}
+impl ActionCost {
+    pub fn standard(ap: i32) -> Self {
+        Self {
+            ap,
+            kind: ActionKind::Standard,
+        }
+    }
+    pub fn bonus(ap: i32) -> Self {
+        Self {
+            ap,
+            kind: ActionKind::Bonus,
+        }
+    }
+    pub fn free() -> Self {
+        Self {
+            ap: 0,
+            kind: ActionKind::Free,
+        }
+    }
+}
+
+///An entity's action points used for the turn based combat system, along with the bonus actions they have left
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
-pub struct ActionPoints(i32);
+pub struct ActionPoints {
+    max: i32,
+    current: i32,
+    bonus: i32,
+}
 impl ActionPoints {
-    pub fn new() -> Self {
-        Self(3)
-    }
-    ///Reduces the action points by the 2AP that a significant action costs if possible
-    ///otherwise just returns the current amount of action points
-    pub fn significant_action(&mut self) -> Result<i32, i32> {
-        if self.0 >= 2 {
-            self.0 -= 2;
-            Ok(self.0)
-        } else {
-            Err(self.0)
+    ///action points for someone who gets this many each turn, see Attributes::action_points
+    pub fn with_max(max: i32) -> Self {
+        Self {
+            max,
+            current: max,
+            bonus: 0,
         }
     }
-    ///Reduces the action points by the 1AP that a minor action costs
-    pub fn minor_action(&mut self) -> Result<i32, i32> {
-        if self.0 >= 1 {
-            self.0 -= 1;
-            Ok(self.0)
-        } else {
-            Err(self.0)
+    ///Checks whether an action could be paid for without paying for it, the error says why not
+    pub fn can_afford(&self, cost: ActionCost) -> Result<(), String> {
+        match cost.kind {
+            ActionKind::Free => Ok(()),
+            ActionKind::Bonus if self.bonus > 0 => Ok(()),
+            _ if self.current >= cost.ap => Ok(()),
+            _ => Err(format!(
+                "it needs {} AP and they only have {}",
+                cost.ap, self.current
+            )),
         }
     }
-    ///consumes all of the character's action points at once
-    pub fn full_turn(&mut self) {
-        self.0 -= 3;
+    ///Pays for an action if possible and returns how many AP are left. Nothing is taken if it can't be paid for, so
+    ///the pool never drops below 0
+    pub fn spend(&mut self, cost: ActionCost) -> Result<i32, String> {
+        self.pay(cost).map(|_paid| self.current)
     }
-    ///Resets the Action Points back to the start/default
-    pub fn reset(&mut self) {
-        self.0 = 3;
+    ///Pays for an action like `spend` but returns what was actually taken, which is what has to be given back to
+    ///refund it. A bonus action paid for out of AP comes back as AP
+    pub fn pay(&mut self, cost: ActionCost) -> Result<ActionCost, String> {
+        self.can_afford(cost)?;
+        let paid = self.payment(cost);
+        match paid.kind {
+            ActionKind::Free => {}
+            ActionKind::Bonus => self.bonus -= 1,
+            ActionKind::Standard => self.current -= paid.ap,
+        }
+        Ok(paid)
+    }
+    ///What an action would actually be paid with, a bonus action falls back to AP once they've all been used
+    pub fn payment(&self, cost: ActionCost) -> ActionCost {
+        match cost.kind {
+            ActionKind::Bonus if self.bonus == 0 => ActionCost::standard(cost.ap),
+            _ => cost,
+        }
+    }
+    ///Gives back an action that's been taken back, in whatever it was paid for with
+    pub fn refund(&mut self, paid: ActionCost) {
+        match paid.kind {
+            ActionKind::Free => {}
+            ActionKind::Bonus => self.bonus += 1,
+            ActionKind::Standard => self.current += paid.ap,
+        }
+    }
+    ///Refills the action points for a new round. The modifier comes from whatever status effects they're under and
+    ///can't take them below 0
+    pub fn reset(&mut self, modifier: i32, bonus_actions: i32) {
+        self.current = (self.max + modifier).max(0);
+        self.bonus = bonus_actions.max(0);
     }
     ///provides how many action points are currently left
     pub fn get(&self) -> i32 {
-        self.0
+        self.current
+    }
+    pub fn max(&self) -> i32 {
+        self.max
+    }
+    ///how many bonus actions are left this round
+    pub fn bonus(&self) -> i32 {
+        self.bonus
     }
 }
 
 ///tag component that points to a weapon and marks it as equipped by an entity that has this component
 pub struct EquippedRangedWeapon(Entity);
+impl EquippedRangedWeapon {
+    pub fn new(weapon: Entity) -> Self {
+        Self(weapon)
+    }
+    ///returns the weapon entity that's equipped
+    pub fn get(&self) -> Entity {
+        self.0
+    }
+}
+
+///Component for a weapon entity that can shoot at things
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct RangedWeapon {
+    ///furthest away in tiles a target can be shot at
+    pub range: i32,
+    ///base chance out of 100 of hitting something at short range before any other modifiers
+    pub accuracy: i32,
+    ///how much health a hit takes off
+    pub damage: Dice,
+    ///the ways it can be fired, single shots are always one of them
+    pub fire_modes: &'static [FireMode],
+}
+impl RangedWeapon {
+    pub fn new(range: i32, accuracy: i32, damage: Dice) -> Self {
+        Self {
+            range,
+            accuracy,
+            damage,
+            fire_modes: &[FireMode::Single],
+        }
+    }
+    ///sets the fire modes the weapon can switch between
+    pub fn with_fire_modes(mut self, fire_modes: &'static [FireMode]) -> Self {
+        self.fire_modes = fire_modes;
+        self
+    }
+    ///returns the fire mode after the one given, wrapping back around to the first
+    pub fn next_fire_mode(&self, current: FireMode) -> FireMode {
+        let index = self
+            .fire_modes
+            .iter()
+            .position(|mode| *mode == current)
+            .map(|index| index + 1)
+            .unwrap_or(0);
+        self.fire_modes
+            .get(index % self.fire_modes.len().max(1))
+            .copied()
+            .unwrap_or(FireMode::Single)
+    }
+}
+
+///How many rounds a ranged weapon puts downrange each time it's fired
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FireMode {
+    Single,
+    Burst,
+    Auto,
+}
+impl FireMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FireMode::Single => "Single shot",
+            FireMode::Burst => "Burst fire",
+            FireMode::Auto => "Auto fire",
+        }
+    }
+    ///how many rounds firing once uses up
+    pub fn rounds(&self) -> i32 {
+        match self {
+            FireMode::Single => 1,
+            FireMode::Burst => 3,
+            FireMode::Auto => 6,
+        }
+    }
+    ///how many separate rolls to hit firing once gets
+    pub fn shots(&self) -> i32 {
+        match self {
+            FireMode::Single => 1,
+            FireMode::Burst => 2,
+            FireMode::Auto => 3,
+        }
+    }
+    ///how much the recoil takes off the chance to hit for each of those shots
+    pub fn modifier(&self) -> i32 {
+        match self {
+            FireMode::Single => 0,
+            FireMode::Burst => -10,
+            FireMode::Auto => -20,
+        }
+    }
+}
+
+///Component for the fire mode a ranged weapon entity is currently set to
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SelectedFireMode(pub FireMode);
+
+///Component for a ranged weapon entity that has to be loaded. Weapons without one never run out
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Magazine {
+    capacity: i32,
+    loaded: i32,
+    ///rounds carried for it that aren't loaded yet
+    spare: i32,
+}
+impl Magazine {
+    ///a full magazine with some spare rounds to reload it with
+    pub fn new(capacity: i32, spare: i32) -> Self {
+        Self {
+            capacity,
+            loaded: capacity,
+            spare,
+        }
+    }
+    ///Takes rounds out of the magazine for firing. If there aren't enough loaded nothing is taken and the
+    ///amount that is loaded is returned instead
+    pub fn fire(&mut self, rounds: i32) -> Result<i32, i32> {
+        if self.loaded < rounds {
+            return Err(self.loaded);
+        }
+        self.loaded -= rounds;
+        Ok(self.loaded)
+    }
+    ///Refills the magazine from the spare rounds and returns how many went in. Fails with the reason if it's
+    ///already full or there's nothing left to load
+    pub fn reload(&mut self) -> Result<i32, &'static str> {
+        if self.loaded >= self.capacity {
+            return Err("it's already full");
+        }
+        if self.spare <= 0 {
+            return Err("there's no spare ammo left");
+        }
+        let rounds = (self.capacity - self.loaded).min(self.spare);
+        self.loaded += rounds;
+        self.spare -= rounds;
+        Ok(rounds)
+    }
+    pub fn can_reload(&self) -> bool {
+        self.loaded < self.capacity && self.spare > 0
+    }
+    pub fn is_empty(&self) -> bool {
+        self.loaded == 0
+    }
+    pub fn loaded(&self) -> i32 {
+        self.loaded
+    }
+    pub fn capacity(&self) -> i32 {
+        self.capacity
+    }
+    pub fn spare(&self) -> i32 {
+        self.spare
+    }
+}
+
+///tag component that points to a melee weapon and marks it as equipped by an entity that has this component
+pub struct EquippedMeleeWeapon(Entity);
+impl EquippedMeleeWeapon {
+    pub fn new(weapon: Entity) -> Self {
+        Self(weapon)
+    }
+    ///returns the weapon entity that's equipped
+    pub fn get(&self) -> Entity {
+        self.0
+    }
+}
+
+///Component for a weapon entity that can hit things standing right next to whoever's holding it
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct MeleeWeapon {
+    ///base chance out of 100 of landing a blow before any other modifiers
+    pub accuracy: i32,
+    ///how much health a hit takes off
+    pub damage: Dice,
+}
+impl MeleeWeapon {
+    pub fn new(accuracy: i32, damage: Dice) -> Self {
+        Self { accuracy, damage }
+    }
+}
+
+///Whether an attack is a shot from a ranged weapon or a blow from a melee weapon
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AttackKind {
+    Ranged,
+    Melee,
+}
+impl AttackKind {
+    ///how the attack is described in the game log, eg. "Vasquez shoots at Raider"
+    pub fn verb(&self) -> &'static str {
+        match self {
+            AttackKind::Ranged => "shoots at",
+            AttackKind::Melee => "swings at",
+        }
+    }
+}
+
+///tag component that points to a throwable item and marks it as ready to throw by an entity that has this component
+pub struct EquippedThrowable(Entity);
+impl EquippedThrowable {
+    pub fn new(item: Entity) -> Self {
+        Self(item)
+    }
+    ///returns the item entity that's ready to throw
+    pub fn get(&self) -> Entity {
+        self.0
+    }
+}
+
+///The shape of the area an explosion covers
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BlastTemplate {
+    ///every tile within the radius of where it lands
+    Circle { radius: i32 },
+    ///a cone spreading out from where it lands, carrying on in the direction it was thrown
+    Cone { length: i32 },
+}
+
+///Kinds of effect an explosion can leave hanging around after it's gone off
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum HazardKind {
+    ///makes anyone standing in it harder to hit
+    Smoke,
+    ///burns anyone standing in it at the start of every round
+    Fire,
+}
+impl HazardKind {
+    pub fn parse(key: &str) -> Option<Self> {
+        match key {
+            "smoke" => Some(HazardKind::Smoke),
+            "fire" => Some(HazardKind::Fire),
+            _ => None,
+        }
+    }
+    pub fn label(&self) -> &'static str {
+        match self {
+            HazardKind::Smoke => "smoke",
+            HazardKind::Fire => "fire",
+        }
+    }
+    ///colour the hazard is shaded over the map with
+    pub fn color(&self) -> Color {
+        match self {
+            HazardKind::Smoke => Color::new(0.8, 0.8, 0.8, 0.5),
+            HazardKind::Fire => Color::new(1.0, 0.45, 0.0, 0.4),
+        }
+    }
+}
+
+///Component for an item that can be thrown and goes off where it lands
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Throwable {
+    ///furthest away in tiles it can be thrown
+    pub range: i32,
+    ///base chance out of 100 of it landing where it was thrown, a miss scatters it somewhere nearby
+    pub accuracy: i32,
+    pub template: BlastTemplate,
+    ///damage done at the centre of the blast
+    pub damage: Dice,
+    ///how much less damage is done for each tile away from the centre
+    pub falloff: i32,
+    ///what it leaves behind on every tile it covered and for how many rounds
+    pub lingering: Option<(HazardKind, i32)>,
+    ///whether it's strong enough to knock down low walls
+    pub destroys_cover: bool,
+}
+
+///Component for a lingering effect left on a tile by an explosion. It's removed once its rounds run out
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Hazard {
+    pub kind: HazardKind,
+    rounds_left: i32,
+}
+impl Hazard {
+    pub fn new(kind: HazardKind, rounds: i32) -> Self {
+        Self {
+            kind,
+            rounds_left: rounds,
+        }
+    }
+    ///counts down a round and returns true if the hazard has run out
+    pub fn tick(&mut self) -> bool {
+        self.rounds_left -= 1;
+        self.rounds_left <= 0
+    }
+}
+
+///tag component for anything that can be carried, dropped on a tile and picked back up
+pub struct Item;
+
+///Component for worn armour that soaks up some of the damage from every hit
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Armour {
+    ///how much damage is taken off each hit, though a hit always does at least 1
+    pub soak: i32,
+}
+
+///The places a combatant can have gear equipped
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum EquipmentSlot {
+    Primary,
+    Sidearm,
+    Armour,
+    Utility,
+}
+impl EquipmentSlot {
+    pub const ALL: [EquipmentSlot; 4] = [
+        EquipmentSlot::Primary,
+        EquipmentSlot::Sidearm,
+        EquipmentSlot::Armour,
+        EquipmentSlot::Utility,
+    ];
+    pub fn label(&self) -> &'static str {
+        match self {
+            EquipmentSlot::Primary => "Primary",
+            EquipmentSlot::Sidearm => "Sidearm",
+            EquipmentSlot::Armour => "Armour",
+            EquipmentSlot::Utility => "Utility",
+        }
+    }
+}
+
+///Component for the gear a combatant has equipped in each slot, and which of their two weapons they have in hand.
+///The Equipped* tags other systems look at are worked out from this, see lib::inventory::refresh_equipped
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Equipment {
+    primary: Option<Entity>,
+    sidearm: Option<Entity>,
+    armour: Option<Entity>,
+    utility: Option<Entity>,
+    ///the weapon slot that's in hand, always either primary or sidearm
+    ready: EquipmentSlot,
+}
+impl Default for Equipment {
+    fn default() -> Self {
+        Self {
+            primary: None,
+            sidearm: None,
+            armour: None,
+            utility: None,
+            ready: EquipmentSlot::Primary,
+        }
+    }
+}
+impl Equipment {
+    pub fn get(&self, slot: EquipmentSlot) -> Option<Entity> {
+        match slot {
+            EquipmentSlot::Primary => self.primary,
+            EquipmentSlot::Sidearm => self.sidearm,
+            EquipmentSlot::Armour => self.armour,
+            EquipmentSlot::Utility => self.utility,
+        }
+    }
+    ///puts an item in a slot, or empties it with None, and returns whatever was there before
+    pub fn set(&mut self, slot: EquipmentSlot, item: Option<Entity>) -> Option<Entity> {
+        let slot = match slot {
+            EquipmentSlot::Primary => &mut self.primary,
+            EquipmentSlot::Sidearm => &mut self.sidearm,
+            EquipmentSlot::Armour => &mut self.armour,
+            EquipmentSlot::Utility => &mut self.utility,
+        };
+        std::mem::replace(slot, item)
+    }
+    ///builder style version of set for loadouts
+    pub fn with(mut self, slot: EquipmentSlot, item: Entity) -> Self {
+        self.set(slot, Some(item));
+        self
+    }
+    ///returns the slot an item is equipped in, if it is
+    pub fn slot_of(&self, item: Entity) -> Option<EquipmentSlot> {
+        EquipmentSlot::ALL
+            .into_iter()
+            .find(|slot| self.get(*slot) == Some(item))
+    }
+    pub fn ready(&self) -> EquipmentSlot {
+        self.ready
+    }
+    ///the weapon slot that isn't in hand
+    pub fn stowed(&self) -> EquipmentSlot {
+        match self.ready {
+            EquipmentSlot::Sidearm => EquipmentSlot::Primary,
+            _ => EquipmentSlot::Sidearm,
+        }
+    }
+    ///switches which weapon slot is in hand
+    pub fn swap_ready(&mut self) {
+        self.ready = self.stowed();
+    }
+}
+
+///Component for everything a combatant is carrying that isn't equipped
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Inventory(Vec<Entity>);
+impl Inventory {
+    pub fn items(&self) -> &Vec<Entity> {
+        &self.0
+    }
+    pub fn add(&mut self, item: Entity) {
+        if !self.0.contains(&item) {
+            self.0.push(item);
+        }
+    }
+    ///takes an item out of the inventory, returning true if it was in there
+    pub fn remove(&mut self, item: Entity) -> bool {
+        let before = self.0.len();
+        self.0.retain(|existing| *existing != item);
+        before != self.0.len()
+    }
+}
+
+///Component for how much punishment an entity can take before it goes down
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Health {
+    max: i32,
+    current: i32,
+}
+impl Health {
+    pub fn new(max: i32) -> Self {
+        Self { max, current: max }
+    }
+    ///takes damage off and returns how much health is left
+    pub fn take_damage(&mut self, damage: i32) -> i32 {
+        self.current = (self.current - damage).max(0);
+        self.current
+    }
+    ///patches them up without going over their max and returns how much health they have now
+    pub fn heal(&mut self, amount: i32) -> i32 {
+        self.current = (self.current + amount.max(0)).min(self.max);
+        self.current
+    }
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0
+    }
+    pub fn current(&self) -> i32 {
+        self.current
+    }
+    pub fn max(&self) -> i32 {
+        self.max
+    }
+}
+
+///The raw physical and mental traits a character has, each scored from 1 to 10 with 5 being average
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Attribute {
+    Agility,
+    Strength,
+    Perception,
+    Willpower,
+}
+///the score an attribute sits at for a completely ordinary person
+pub const AVERAGE_ATTRIBUTE: i32 = 5;
+
+///Component holding a character's attributes. Everything that used to be a flat number for every character (how far
+///they move, how many actions they get, how quickly they react) is worked out from these
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Attributes {
+    pub agility: i32,
+    pub strength: i32,
+    pub perception: i32,
+    pub willpower: i32,
+}
+impl Default for Attributes {
+    fn default() -> Self {
+        Self::new(
+            AVERAGE_ATTRIBUTE,
+            AVERAGE_ATTRIBUTE,
+            AVERAGE_ATTRIBUTE,
+            AVERAGE_ATTRIBUTE,
+        )
+    }
+}
+impl Attributes {
+    pub fn new(agility: i32, strength: i32, perception: i32, willpower: i32) -> Self {
+        Self {
+            agility,
+            strength,
+            perception,
+            willpower,
+        }
+    }
+    pub fn get(&self, attribute: Attribute) -> i32 {
+        match attribute {
+            Attribute::Agility => self.agility,
+            Attribute::Strength => self.strength,
+            Attribute::Perception => self.perception,
+            Attribute::Willpower => self.willpower,
+        }
+    }
+    ///how far above or below average an attribute is, which is what most things that use them care about
+    pub fn modifier(&self, attribute: Attribute) -> i32 {
+        self.get(attribute) - AVERAGE_ATTRIBUTE
+    }
+    ///How many tiles they can move each time they move. An average character gets 3 and every 2 points of agility
+    ///changes it by one, though everyone can manage at least a single step
+    pub fn movement_allowance(&self) -> i32 {
+        (1 + self.agility / 2).max(1)
+    }
+    ///How many action points they get each turn. Everyone gets 3 unless their willpower is exceptional one way or the
+    ///other
+    pub fn action_points(&self) -> i32 {
+        (3 + self.modifier(Attribute::Willpower) / 5).max(1)
+    }
+    ///what's added to their initiative roll, quick reflexes and sharp eyes both help them get going first
+    pub fn initiative_bonus(&self) -> i32 {
+        self.modifier(Attribute::Agility) + self.modifier(Attribute::Perception)
+    }
+}
+
+///Things a character can get better at with training, each one leaning on one of their attributes
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Skill {
+    ///shooting straight
+    Marksmanship,
+    ///fighting up close
+    Melee,
+    ///running, climbing, throwing
+    Athletics,
+    ///keeping it together under fire
+    Resolve,
+}
+impl Skill {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Skill::Marksmanship => "Marksmanship",
+            Skill::Melee => "Melee",
+            Skill::Athletics => "Athletics",
+            Skill::Resolve => "Resolve",
+        }
+    }
+    ///the attribute the skill is built on
+    pub fn attribute(&self) -> Attribute {
+        match self {
+            Skill::Marksmanship => Attribute::Perception,
+            Skill::Melee => Attribute::Strength,
+            Skill::Athletics => Attribute::Agility,
+            Skill::Resolve => Attribute::Willpower,
+        }
+    }
+}
+
+///Component holding how many ranks a character has in each skill, anything not listed is untrained (0 ranks)
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Skills {
+    ranks: HashMap<Skill, i32>,
+}
+impl Skills {
+    pub fn with(mut self, skill: Skill, ranks: i32) -> Self {
+        self.ranks.insert(skill, ranks);
+        self
+    }
+    pub fn rank(&self, skill: Skill) -> i32 {
+        self.ranks.get(&skill).copied().unwrap_or(0)
+    }
+}
+
+///How much protection something offers from attacks coming from the other side of it
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum CoverLevel {
+    None,
+    Quarter,
+    Half,
+    Full,
+}
+impl CoverLevel {
+    pub fn label(&self) -> &'static str {
+        match self {
+            CoverLevel::None => "No cover",
+            CoverLevel::Quarter => "Quarter cover",
+            CoverLevel::Half => "Half cover",
+            CoverLevel::Full => "Full cover",
+        }
+    }
+    ///how much cover takes off the chance to hit
+    pub fn modifier(&self) -> i32 {
+        match self {
+            CoverLevel::None => 0,
+            CoverLevel::Quarter => -10,
+            CoverLevel::Half => -25,
+            CoverLevel::Full => -40,
+        }
+    }
+    ///what percentage of an explosion's damage the cover soaks up
+    pub fn blast_reduction(&self) -> i32 {
+        match self {
+            CoverLevel::None => 0,
+            CoverLevel::Quarter => 25,
+            CoverLevel::Half => 50,
+            CoverLevel::Full => 100,
+        }
+    }
+}
+
+///Component for a prop that combatants can take cover behind
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Cover(pub CoverLevel);
+
+///Conditions a combatant can be under that change how easy they are to hit
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum StatusEffect {
+    ///braced behind cover and making themselves as small as possible
+    Hunkered,
+    ///reeling and unable to get out of the way
+    Stunned,
+    ///hidden in a cloud of smoke
+    InSmoke,
+}
+impl StatusEffect {
+    pub const ALL: [StatusEffect; 3] = [
+        StatusEffect::Hunkered,
+        StatusEffect::Stunned,
+        StatusEffect::InSmoke,
+    ];
+    ///the name the status goes by in data files
+    pub fn key(&self) -> &'static str {
+        match self {
+            StatusEffect::Hunkered => "hunkered",
+            StatusEffect::Stunned => "stunned",
+            StatusEffect::InSmoke => "in_smoke",
+        }
+    }
+    pub fn parse(key: &str) -> Option<Self> {
+        StatusEffect::ALL
+            .into_iter()
+            .find(|status| status.key() == key)
+    }
+    ///how much the status changes the AP someone starts the round with when the actions file doesn't say otherwise
+    pub fn default_ap_modifier(&self) -> i32 {
+        match self {
+            StatusEffect::Stunned => -1,
+            StatusEffect::Hunkered | StatusEffect::InSmoke => 0,
+        }
+    }
+    pub fn label(&self) -> &'static str {
+        match self {
+            StatusEffect::Hunkered => "hunkered down",
+            StatusEffect::Stunned => "stunned",
+            StatusEffect::InSmoke => "in smoke",
+        }
+    }
+    ///how much the status changes the chance of attacks against whoever has it hitting
+    pub fn defence_modifier(&self) -> i32 {
+        match self {
+            StatusEffect::Hunkered => -20,
+            StatusEffect::Stunned => 15,
+            StatusEffect::InSmoke => -20,
+        }
+    }
+}
+
+///Component counting down the rounds left on any status effects that wear off by themselves, ticked at the start
+///of its holder's turn
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct StatusTimers(Vec<(StatusEffect, i32)>);
+impl StatusTimers {
+    ///starts a status counting down, or tops it up if it's already running and has less time left
+    pub fn add(&mut self, status: StatusEffect, rounds: i32) {
+        match self
+            .0
+            .iter_mut()
+            .find(|(existing, _rounds)| *existing == status)
+        {
+            Some((_status, rounds_left)) => *rounds_left = (*rounds_left).max(rounds),
+            None => self.0.push((status, rounds)),
+        }
+    }
+    pub fn remove(&mut self, status: StatusEffect) {
+        self.0.retain(|(existing, _rounds)| *existing != status);
+    }
+    ///counts every status down a round and returns the ones that have worn off
+    pub fn tick(&mut self) -> Vec<StatusEffect> {
+        for (_status, rounds_left) in self.0.iter_mut() {
+            *rounds_left -= 1;
+        }
+        let expired: Vec<StatusEffect> = self
+            .0
+            .iter()
+            .filter(|(_status, rounds_left)| *rounds_left <= 0)
+            .map(|(status, _rounds)| *status)
+            .collect();
+        self.0.retain(|(_status, rounds_left)| *rounds_left > 0);
+        expired
+    }
+}
+
+///Component listing the status effects an entity is currently under
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct StatusEffects(Vec<StatusEffect>);
+impl StatusEffects {
+    pub fn get(&self) -> &Vec<StatusEffect> {
+        &self.0
+    }
+    pub fn has(&self, status: StatusEffect) -> bool {
+        self.0.contains(&status)
+    }
+    pub fn add(&mut self, status: StatusEffect) {
+        if !self.has(status) {
+            self.0.push(status);
+        }
+    }
+    pub fn remove(&mut self, status: StatusEffect) {
+        self.0.retain(|existing| *existing != status);
+    }
+}
+
+///Component for the targeting reticule, a marker the player steers around the map to pick what to shoot at.
+///It doesn't collide with anything and is despawned once a target is confirmed or targeting is cancelled
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Reticule {
+    ///who's doing the aiming
+    owner: Entity,
+}
+impl Reticule {
+    pub fn new(owner: Entity) -> Self {
+        Self { owner }
+    }
+    pub fn get_owner(&self) -> Entity {
+        self.owner
+    }
+}
+
+///Component for a reticule that's picking the target of an ability rather than a shot, holding which of the owner's
+///abilities it's for
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct AimingAbility(pub usize);
+
+///Component listing the abilities a combatant knows, by their key in the ability registry, and how many rounds are
+///left before each one can be used again
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Abilities {
+    known: Vec<String>,
+    cooldowns: HashMap<String, i32>,
+}
+impl Abilities {
+    pub fn new(keys: &[&str]) -> Self {
+        Self {
+            known: keys.iter().map(|key| key.to_string()).collect(),
+            cooldowns: HashMap::new(),
+        }
+    }
+    ///the abilities in the order they're shown on the action bar
+    pub fn known(&self) -> &Vec<String> {
+        &self.known
+    }
+    ///returns the key of the ability in a slot on the action bar
+    pub fn slot(&self, slot: usize) -> Option<&str> {
+        self.known.get(slot).map(|key| key.as_str())
+    }
+    ///rounds left before an ability can be used again, 0 if it's ready
+    pub fn cooldown(&self, key: &str) -> i32 {
+        self.cooldowns.get(key).copied().unwrap_or(0)
+    }
+    pub fn start_cooldown(&mut self, key: &str, rounds: i32) {
+        if rounds > 0 {
+            self.cooldowns.insert(key.to_string(), rounds);
+        }
+    }
+    ///counts every cooldown down a round
+    pub fn tick(&mut self) {
+        for rounds_left in self.cooldowns.values_mut() {
+            *rounds_left -= 1;
+        }
+        self.cooldowns.retain(|_key, rounds_left| *rounds_left > 0);
+    }
+}
+
+///how wide the cone is that someone on overwatch watches when they're pointed in a direction
+pub const OVERWATCH_CONE_DEGREES: f32 = 90.0;
+///Component for a combatant holding their fire to take a shot at the first hostile that moves where they can see it.
+///With a direction they only watch the cone facing that way, without one they watch all the way around
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Overwatch {
+    direction: Option<IVec2>,
+}
+impl Overwatch {
+    pub fn all_round() -> Self {
+        Self { direction: None }
+    }
+    ///watches the cone facing along the direction, or all round if there isn't one
+    pub fn toward(direction: IVec2) -> Self {
+        if direction == IVec2::ZERO {
+            return Self::all_round();
+        }
+        Self {
+            direction: Some(direction),
+        }
+    }
+    pub fn direction(&self) -> Option<IVec2> {
+        self.direction
+    }
+    ///checks if a tile is inside the cone watched from a position. Says nothing about range or line of sight
+    pub fn watches(&self, from: IVec2, tile: IVec2) -> bool {
+        let Some(direction) = self.direction else {
+            return true;
+        };
+        let offset = (tile - from).as_vec2();
+        if offset == Vec2::ZERO {
+            return true;
+        }
+        let half_angle = (OVERWATCH_CONE_DEGREES / 2.0).to_radians();
+        direction.as_vec2().normalize().dot(offset.normalize()) >= half_angle.cos() - f32::EPSILON
+    }
+}
 
 ///Component assigned to an entity when it's in the moving phase,
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -168,14 +1189,11 @@ pub struct MovementPoints {
     current: i32,
 }
 impl MovementPoints {
-    ///create a custom amount of movement points for non-standard enemies
+    ///creates movement points for someone who can move this many tiles each time they move, see
+    ///Attributes::movement_allowance
     pub fn new(max: i32) -> Self {
         Self { max, current: max }
     }
-    ///create the movement point component for a standard humanoid character (4 tiles or 6 meters)
-    pub fn default() -> Self {
-        Self { max: 3, current: 3 }
-    }
     ///reduces the movement points by one. used every time an entity moves. an entity will never
     ///consume more than one movement point at a time
     pub fn decrement(&mut self) {
@@ -187,35 +1205,275 @@ impl MovementPoints {
     }
     ///checks if there's any movement points left
     pub fn can_move(&self) -> bool {
-        if self.current > 0 {
-            true
-        } else {
-            false
-        }
+        self.current > 0
     }
     ///returns the current amount of movement points
     pub fn current(&self) -> i32 {
         self.current
     }
 }
+///A single step an entity has taken this turn, along with the movement points it had before taking it so the step
+///can be put back the way it was if it's undone
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct MoveRecord {
+    pub from: IVec2,
+    pub to: IVec2,
+    pub movement_points: MovementPoints,
+    ///what was paid to start moving, only ever set on the first step of a move so undoing it gives it back
+    pub move_cost: Option<ActionCost>,
+}
+///Component holding every step an entity has taken this turn that can still be taken back. It's cleared whenever
+///they do something that can't be undone, like shooting or ending their turn
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct MoveHistory {
+    steps: Vec<MoveRecord>,
+}
+impl MoveHistory {
+    ///adds a step to the history, the first one of a move carries what was paid to start it
+    pub fn push(
+        &mut self,
+        from: IVec2,
+        to: IVec2,
+        movement_points: MovementPoints,
+        move_cost: Option<ActionCost>,
+    ) {
+        self.steps.push(MoveRecord {
+            from,
+            to,
+            movement_points,
+            move_cost,
+        });
+    }
+    ///the step that would be undone next
+    pub fn last(&self) -> Option<&MoveRecord> {
+        self.steps.last()
+    }
+    ///takes the most recent step off the history so it can be undone
+    pub fn pop(&mut self) -> Option<MoveRecord> {
+        self.steps.pop()
+    }
+    pub fn clear(&mut self) {
+        self.steps.clear();
+    }
+}
 ///Component spawned to add a string to debug log used to track game/engine behavior
-///that can be displayed if a flag is set
+///that can be displayed depending on its level and the subsystem it came from
 //this structure allows us to be agnostic about how these messages are displayed or handled in the engine
-pub struct DebugLogMessage(pub String);
+pub struct DebugLogMessage {
+    pub level: DebugLevel,
+    pub subsystem: Subsystem,
+    pub contents: String,
+}
 
 impl DebugLogMessage {
-    pub fn new(contents: String) -> Self {
-        Self(contents)
+    pub fn new(level: DebugLevel, subsystem: Subsystem, contents: String) -> Self {
+        Self {
+            level,
+            subsystem,
+            contents,
+        }
     }
 }
 ///Component spawned to add a string to the game log that will be displayed in the UI
 //this structure allows us to handle the game log different ways as the engine evolves
-pub struct GameLogMessage(pub String);
+pub struct GameLogMessage {
+    pub category: LogCategory,
+    pub contents: String,
+}
 
 impl GameLogMessage {
-    pub fn new(contents: String) -> Self {
-        Self(contents)
+    pub fn new(category: LogCategory, contents: String) -> Self {
+        Self { category, contents }
+    }
+}
+///Component spawned to ask for a sound effect to be played, the audio system decides how loud it is
+///(or whether it's played at all if the game is muted)
+pub struct SoundEffect {
+    pub category: SoundCategory,
+    pub sound: String,
+}
+
+impl SoundEffect {
+    pub fn new(category: SoundCategory, sound: &str) -> Self {
+        Self {
+            category,
+            sound: sound.to_string(),
+        }
     }
 }
 ///tag struct to mark something as having collision
 pub struct Collideable;
+
+///Component tracking where an entity is drawn, in tile units, separately from the IVec2 the game logic uses.
+///When the logical position jumps to a new tile this slides from where it was to where it's going over a set duration
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct VisualPosition {
+    from: Vec2,
+    to: Vec2,
+    elapsed: f32,
+    duration: f32,
+}
+impl VisualPosition {
+    ///creates a visual position that's sitting still on a tile
+    pub fn at(tile: IVec2) -> Self {
+        Self {
+            from: tile.as_vec2(),
+            to: tile.as_vec2(),
+            elapsed: 0.0,
+            duration: 0.0,
+        }
+    }
+    ///starts sliding from wherever it's currently drawn towards a new tile over the given number of seconds
+    pub fn move_to(&mut self, tile: IVec2, duration: f32) {
+        self.from = self.current();
+        self.to = tile.as_vec2();
+        self.elapsed = 0.0;
+        self.duration = duration;
+    }
+    ///advances the animation by the time since the last frame
+    pub fn update(&mut self, delta_time: f32) {
+        self.elapsed = (self.elapsed + delta_time).min(self.duration);
+    }
+    ///jumps straight to the end of the animation
+    pub fn finish(&mut self) {
+        self.elapsed = self.duration;
+    }
+    ///where the entity should be drawn right now, in tile units
+    pub fn current(&self) -> Vec2 {
+        if self.duration <= 0.0 {
+            return self.to;
+        }
+        let t = self.elapsed / self.duration;
+        //ease out so the slide settles into the tile rather than stopping dead
+        let eased = 1.0 - (1.0 - t) * (1.0 - t);
+        self.from.lerp(self.to, eased)
+    }
+    pub fn is_animating(&self) -> bool {
+        self.elapsed < self.duration
+    }
+}
+
+///Component that briefly tints an entity a colour, used to show that it's attacking or has been hit.
+///Removed by the animation system once it's finished
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Flash {
+    color: Color,
+    elapsed: f32,
+    duration: f32,
+}
+impl Flash {
+    pub fn new(color: Color, duration: f32) -> Self {
+        Self {
+            color,
+            elapsed: 0.0,
+            duration,
+        }
+    }
+    ///the flash used when an entity makes an attack
+    pub fn attack() -> Self {
+        Self::new(WHITE, 0.2)
+    }
+    ///the flash used when an entity is hit by an attack
+    pub fn hit() -> Self {
+        Self::new(Color::new(1.0, 0.15, 0.15, 1.0), 0.3)
+    }
+    pub fn update(&mut self, delta_time: f32) {
+        self.elapsed = (self.elapsed + delta_time).min(self.duration);
+    }
+    pub fn finish(&mut self) {
+        self.elapsed = self.duration;
+    }
+    pub fn is_animating(&self) -> bool {
+        self.elapsed < self.duration
+    }
+    ///blends the entity's normal tint towards the flash colour, fading out over the duration of the flash
+    pub fn apply(&self, base: Color) -> Color {
+        let strength = if self.duration > 0.0 {
+            1.0 - self.elapsed / self.duration
+        } else {
+            0.0
+        };
+        Color::new(
+            base.r + (self.color.r - base.r) * strength,
+            base.g + (self.color.g - base.g) * strength,
+            base.b + (self.color.b - base.b) * strength,
+            base.a,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spending_never_takes_the_pool_below_zero() {
+        let mut ap = ActionPoints::with_max(3);
+        assert_eq!(ap.spend(ActionCost::standard(2)), Ok(1));
+        assert!(ap.spend(ActionCost::standard(2)).is_err());
+        assert_eq!(ap.get(), 1);
+        assert_eq!(ap.spend(ActionCost::free()), Ok(1));
+    }
+
+    #[test]
+    fn bonus_actions_fall_back_to_ap_once_used_up() {
+        let mut ap = ActionPoints::with_max(3);
+        ap.reset(0, 1);
+        assert_eq!(ap.payment(ActionCost::bonus(1)), ActionCost::bonus(1));
+        assert_eq!(ap.spend(ActionCost::bonus(1)), Ok(3));
+        assert_eq!(ap.payment(ActionCost::bonus(1)), ActionCost::standard(1));
+        assert_eq!(ap.spend(ActionCost::bonus(1)), Ok(2));
+        //status effects can't leave anyone with less than nothing
+        ap.reset(-5, 0);
+        assert_eq!(ap.get(), 0);
+    }
+
+    #[test]
+    fn refunds_give_back_exactly_what_was_paid() {
+        let mut ap = ActionPoints::with_max(3);
+        ap.reset(0, 1);
+        let before = ap;
+        let paid = ap.pay(ActionCost::standard(1)).unwrap();
+        ap.refund(paid);
+        assert_eq!(ap, before);
+        let paid = ap.pay(ActionCost::bonus(1)).unwrap();
+        assert_eq!(ap.bonus(), 0);
+        ap.refund(paid);
+        assert_eq!(ap, before);
+    }
+
+    #[test]
+    fn steps_are_undone_latest_first() {
+        let mut history = MoveHistory::default();
+        history.push(
+            IVec2::new(0, 0),
+            IVec2::new(1, 0),
+            MovementPoints::new(3),
+            Some(ActionCost::standard(1)),
+        );
+        history.push(
+            IVec2::new(1, 0),
+            IVec2::new(2, 0),
+            MovementPoints::new(3),
+            None,
+        );
+        assert_eq!(
+            history.last().map(|record| record.to),
+            Some(IVec2::new(2, 0))
+        );
+        assert_eq!(history.pop().map(|record| record.move_cost), Some(None));
+        assert_eq!(
+            history.pop().map(|record| record.move_cost),
+            Some(Some(ActionCost::standard(1)))
+        );
+        assert_eq!(history.pop(), None);
+        history.push(
+            IVec2::new(0, 0),
+            IVec2::new(1, 0),
+            MovementPoints::new(3),
+            None,
+        );
+        history.clear();
+        assert_eq!(history.last(), None);
+    }
+}
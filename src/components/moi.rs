@@ -1,4 +1,5 @@
 //this file will contain all of the messages of intent used by the game
+use super::AttackKind;
 use hecs::*;
 use macroquad::prelude::*;
 #[derive(Copy, Clone, Debug)]
@@ -21,3 +22,51 @@ impl MOIWantsToMove {
 }
 ///Simple message component for communicating that the current character wants to end their turn
 pub struct MOIEndTurn;
+///Message component for an entity wanting to attack another with one of their equipped weapons
+#[derive(Copy, Clone, Debug)]
+pub struct MOIAttack {
+    attacker: Entity,
+    target: Entity,
+    kind: AttackKind,
+}
+impl MOIAttack {
+    pub fn new(attacker: Entity, target: Entity, kind: AttackKind) -> Self {
+        Self {
+            attacker,
+            target,
+            kind,
+        }
+    }
+    pub fn get(&self) -> (Entity, Entity, AttackKind) {
+        (self.attacker, self.target, self.kind)
+    }
+}
+///Message component for an entity wanting to throw their equipped throwable at a tile
+#[derive(Copy, Clone, Debug)]
+pub struct MOIThrow {
+    thrower: Entity,
+    target: IVec2,
+}
+impl MOIThrow {
+    pub fn new(thrower: Entity, target: IVec2) -> Self {
+        Self { thrower, target }
+    }
+    pub fn get(&self) -> (Entity, IVec2) {
+        (self.thrower, self.target)
+    }
+}
+///Message component for an entity using one of their abilities, by its slot on their action bar, on a tile
+#[derive(Copy, Clone, Debug)]
+pub struct MOIUseAbility {
+    user: Entity,
+    slot: usize,
+    target: IVec2,
+}
+impl MOIUseAbility {
+    pub fn new(user: Entity, slot: usize, target: IVec2) -> Self {
+        Self { user, slot, target }
+    }
+    pub fn get(&self) -> (Entity, usize, IVec2) {
+        (self.user, self.slot, self.target)
+    }
+}
@@ -0,0 +1,128 @@
+/*
+Settings for a single run of the sandbox. They're read from the command line so they can be changed
+without recompiling, eg. `fixing_turnbased --debug-level=trace --debug-only=movement,turn --debug-file=debug.log
+--combat-log=combat.log --move-duration=0.2`
+*/
+use crate::audio::SoundCategory;
+use crate::debug_log::*;
+use crate::init_ecs::DEFAULT_SCENARIO;
+use crate::map::DEFAULT_MAP_PATH;
+
+pub struct Config {
+    ///least important debug message that's shown, None switches the debug log off
+    pub debug_level: Option<DebugLevel>,
+    ///the only subsystems debug messages are shown for, None means all of them
+    pub debug_subsystems: Option<Vec<Subsystem>>,
+    ///file debug messages are appended to as well as printed
+    pub debug_file: Option<String>,
+    ///file the combat log is exported to, None names each export after when it was made
+    pub combat_log_file: Option<String>,
+    ///how many seconds it takes a character to slide from one tile to the next
+    pub move_duration: f32,
+    ///plays sounds through a backend that doesn't need an audio device, for headless runs
+    pub silent: bool,
+    ///starts the game with sound muted, it can still be unmuted in game
+    pub muted: bool,
+    ///volumes given for individual sound categories with `--volume-<category>=<0.0 to 1.0>`
+    pub volumes: Vec<(SoundCategory, f32)>,
+    ///map file the encounter is fought on, relative to the resources folder
+    pub map_path: String,
+    ///watches the resources folder and reloads anything that changes while the game is running.
+    ///on by default in debug builds, `--hot-reload` and `--no-hot-reload` override that
+    pub hot_reload: bool,
+    ///seed for all of the game's randomness, picked from the clock if it isn't given
+    pub seed: Option<u64>,
+    ///which set of combatants and props the encounter starts with
+    pub scenario: String,
+    ///replay file to play back instead of taking input, it brings its own seed, scenario and map
+    pub replay_path: Option<String>,
+    ///where the replay of this run is saved when the player quits, picked from the clock if it isn't given
+    pub record_path: Option<String>,
+}
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            debug_level: Some(DebugLevel::Info),
+            debug_subsystems: None,
+            debug_file: None,
+            combat_log_file: None,
+            move_duration: 0.15,
+            silent: false,
+            muted: false,
+            volumes: Vec::new(),
+            map_path: String::from(DEFAULT_MAP_PATH),
+            hot_reload: cfg!(debug_assertions),
+            seed: None,
+            scenario: String::from(DEFAULT_SCENARIO),
+            replay_path: None,
+            record_path: None,
+        }
+    }
+}
+impl Config {
+    ///reads the config from the arguments the game was launched with
+    pub fn from_args() -> Self {
+        Self::parse(std::env::args().skip(1))
+    }
+    ///Builds a config from a list of `--key=value` arguments. Anything that isn't understood is reported and
+    ///skipped rather than stopping the game from launching
+    pub fn parse(args: impl Iterator<Item = String>) -> Self {
+        let mut config = Config::default();
+        for arg in args {
+            let (key, value) = match arg.split_once('=') {
+                Some((key, value)) => (key.to_string(), value.to_string()),
+                None => (arg.clone(), String::new()),
+            };
+            match key.as_str() {
+                "--debug-level" => {
+                    if value == "off" {
+                        config.debug_level = None;
+                    } else if let Some(level) = DebugLevel::parse(&value) {
+                        config.debug_level = Some(level);
+                    } else {
+                        println!("unknown debug level {}", value);
+                    }
+                }
+                "--debug-only" => {
+                    let mut subsystems: Vec<Subsystem> = Vec::new();
+                    for name in value.split(',') {
+                        match Subsystem::parse(name) {
+                            Some(subsystem) => subsystems.push(subsystem),
+                            None => println!("unknown debug subsystem {}", name),
+                        }
+                    }
+                    config.debug_subsystems = Some(subsystems);
+                }
+                "--debug-file" => config.debug_file = Some(value),
+                "--combat-log" => config.combat_log_file = Some(value),
+                "--move-duration" => match value.parse::<f32>() {
+                    Ok(duration) if duration >= 0.0 => config.move_duration = duration,
+                    _ => println!("invalid move duration {}", value),
+                },
+                "--map" => config.map_path = value,
+                "--hot-reload" => config.hot_reload = true,
+                "--no-hot-reload" => config.hot_reload = false,
+                "--seed" => match value.parse::<u64>() {
+                    Ok(seed) => config.seed = Some(seed),
+                    Err(_) => println!("invalid seed {}", value),
+                },
+                "--scenario" => config.scenario = value,
+                "--replay" => config.replay_path = Some(value),
+                "--record" => config.record_path = Some(value),
+                "--silent" => config.silent = true,
+                "--mute" => config.muted = true,
+                _ if key.starts_with("--volume-") => {
+                    let category = SoundCategory::parse(key.trim_start_matches("--volume-"));
+                    match (category, value.parse::<f32>()) {
+                        (Some(category), Ok(volume)) => {
+                            config.volumes.push((category, volume.clamp(0.0, 1.0)))
+                        }
+                        _ => println!("invalid volume setting {}", arg),
+                    }
+                }
+                _ => println!("unknown argument {}", arg),
+            }
+        }
+        config
+    }
+}
@@ -0,0 +1,148 @@
+/*
+This module handles the debug log used to trace game/engine behaviour. Every message has a level and the subsystem
+it came from so the noise can be filtered down to whatever's actually being debugged, and it can optionally be
+appended to a file so a run can be looked over after the fact
+*/
+use crate::config::Config;
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+
+///How important a debug message is. Ordered from least to most important so filtering is just a comparison
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum DebugLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+impl DebugLevel {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DebugLevel::Trace => "TRACE",
+            DebugLevel::Debug => "DEBUG",
+            DebugLevel::Info => "INFO",
+            DebugLevel::Warn => "WARN",
+            DebugLevel::Error => "ERROR",
+        }
+    }
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "trace" => Some(DebugLevel::Trace),
+            "debug" => Some(DebugLevel::Debug),
+            "info" => Some(DebugLevel::Info),
+            "warn" => Some(DebugLevel::Warn),
+            "error" => Some(DebugLevel::Error),
+            _ => None,
+        }
+    }
+}
+
+///Which part of the game a debug message came from
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Subsystem {
+    Input,
+    Movement,
+    Turn,
+    Ai,
+    Combat,
+    Render,
+    Assets,
+    Audio,
+}
+impl Subsystem {
+    pub const ALL: [Subsystem; 8] = [
+        Subsystem::Input,
+        Subsystem::Movement,
+        Subsystem::Turn,
+        Subsystem::Ai,
+        Subsystem::Combat,
+        Subsystem::Render,
+        Subsystem::Assets,
+        Subsystem::Audio,
+    ];
+    pub fn label(&self) -> &'static str {
+        match self {
+            Subsystem::Input => "input",
+            Subsystem::Movement => "movement",
+            Subsystem::Turn => "turn",
+            Subsystem::Ai => "ai",
+            Subsystem::Combat => "combat",
+            Subsystem::Render => "render",
+            Subsystem::Assets => "assets",
+            Subsystem::Audio => "audio",
+        }
+    }
+    pub fn parse(name: &str) -> Option<Self> {
+        Subsystem::ALL
+            .iter()
+            .find(|subsystem| subsystem.label() == name.to_lowercase())
+            .copied()
+    }
+}
+
+///Decides which debug messages are shown and writes them out to the console and the log file if there is one
+pub struct DebugLogger {
+    ///the least important level that still gets shown, None means the debug log is switched off entirely
+    min_level: Option<DebugLevel>,
+    subsystems: HashSet<Subsystem>,
+    file: Option<File>,
+}
+impl DebugLogger {
+    pub fn new(config: &Config) -> Self {
+        let subsystems = match &config.debug_subsystems {
+            Some(subsystems) => subsystems.iter().copied().collect(),
+            None => Subsystem::ALL.iter().copied().collect(),
+        };
+        let file = config.debug_file.as_ref().and_then(|path| {
+            match OpenOptions::new().create(true).append(true).open(path) {
+                Ok(file) => Some(file),
+                Err(error) => {
+                    println!("couldn't open debug log file {}: {}", path, error);
+                    None
+                }
+            }
+        });
+        Self {
+            min_level: config.debug_level,
+            subsystems,
+            file,
+        }
+    }
+    ///checks if a message at this level from this subsystem would make it through the filter
+    pub fn allows(&self, level: DebugLevel, subsystem: Subsystem) -> bool {
+        match self.min_level {
+            Some(min_level) => level >= min_level && self.subsystems.contains(&subsystem),
+            None => false,
+        }
+    }
+    ///prints the message and appends it to the log file if it makes it through the filter
+    pub fn log(&mut self, level: DebugLevel, subsystem: Subsystem, contents: &str) {
+        if !self.allows(level, subsystem) {
+            return;
+        }
+        let line = format!("[{}][{}] {}", level.label(), subsystem.label(), contents);
+        println!("{}", line);
+        if let Some(file) = self.file.as_mut() {
+            if let Err(error) = writeln!(file, "{}", line) {
+                println!("couldn't write to debug log file: {}", error);
+                self.file = None;
+            }
+        }
+    }
+    ///Steps the filter through each level from most to least verbose and then off, wrapping back around to trace
+    pub fn cycle_level(&mut self) {
+        self.min_level = match self.min_level {
+            Some(DebugLevel::Trace) => Some(DebugLevel::Debug),
+            Some(DebugLevel::Debug) => Some(DebugLevel::Info),
+            Some(DebugLevel::Info) => Some(DebugLevel::Warn),
+            Some(DebugLevel::Warn) => Some(DebugLevel::Error),
+            Some(DebugLevel::Error) => None,
+            None => Some(DebugLevel::Trace),
+        };
+    }
+    pub fn min_level(&self) -> Option<DebugLevel> {
+        self.min_level
+    }
+}
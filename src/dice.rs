@@ -0,0 +1,222 @@
+/*
+This module holds every bit of randomness in the game. All of it comes from one seed so an encounter can be played
+out again exactly, and each subsystem draws from its own stream so adding a roll in one place doesn't shift the
+results everywhere else
+*/
+use std::collections::HashMap;
+use std::fmt;
+
+///Small, fast random number generator (SplitMix64). Plenty good enough for dice and easy to reproduce from a seed
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+    ///returns a number from `low` up to and including `high`
+    pub fn range(&mut self, low: i32, high: i32) -> i32 {
+        if high <= low {
+            return low;
+        }
+        let span = (high as i64 - low as i64 + 1) as u64;
+        low + (self.next_u64() % span) as i32
+    }
+}
+
+///The parts of the game that roll dice. Each one gets its own stream of numbers from the seed
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum RngStream {
+    Initiative,
+    Attack,
+    Damage,
+    Scatter,
+    Skill,
+}
+impl RngStream {
+    ///A fixed number for each stream that's mixed into the seed. These are written out rather than taken from the
+    ///order of the enum so adding a new stream can never change the numbers an existing one produces
+    fn salt(&self) -> u64 {
+        match self {
+            RngStream::Initiative => 0x1A17_1A71,
+            RngStream::Attack => 0xA77A_C4ED,
+            RngStream::Damage => 0xDA3A_6E00,
+            RngStream::Scatter => 0x5CA7_7E42,
+            RngStream::Skill => 0x5C11_1C4E,
+        }
+    }
+}
+
+///Dice written in tabletop notation, eg. "2d6+1" is two six sided dice plus one
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Dice {
+    pub count: i32,
+    pub sides: i32,
+    pub modifier: i32,
+}
+impl Dice {
+    pub fn new(count: i32, sides: i32, modifier: i32) -> Self {
+        Self {
+            count,
+            sides,
+            modifier,
+        }
+    }
+    ///a flat number that doesn't need rolling
+    pub fn flat(value: i32) -> Self {
+        Self::new(0, 0, value)
+    }
+    ///the highest total these dice can roll
+    pub fn max(&self) -> i32 {
+        self.count * self.sides + self.modifier
+    }
+    ///Reads dice notation like "2d6+1", "d20", "3d4-2" or just "5". Whitespace is ignored
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let cleaned: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+        let cleaned = cleaned.to_lowercase();
+        let invalid = || format!("\"{}\" isn't valid dice notation", text);
+        let Some((count, rest)) = cleaned.split_once('d') else {
+            return cleaned
+                .parse::<i32>()
+                .map(Dice::flat)
+                .map_err(|_| invalid());
+        };
+        let count = if count.is_empty() {
+            1
+        } else {
+            count.parse::<i32>().map_err(|_| invalid())?
+        };
+        let (sides, modifier) = match rest.find(['+', '-']) {
+            Some(split) => (
+                &rest[..split],
+                rest[split..].parse::<i32>().map_err(|_| invalid())?,
+            ),
+            None => (rest, 0),
+        };
+        let sides = sides.parse::<i32>().map_err(|_| invalid())?;
+        if count < 1 || sides < 1 {
+            return Err(invalid());
+        }
+        Ok(Self::new(count, sides, modifier))
+    }
+    pub fn roll(&self, rng: &mut Rng) -> i32 {
+        let mut total = self.modifier;
+        for _ in 0..self.count {
+            total += rng.range(1, self.sides);
+        }
+        total
+    }
+}
+impl fmt::Display for Dice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.count == 0 {
+            return write!(f, "{}", self.modifier);
+        }
+        write!(f, "{}d{}", self.count, self.sides)?;
+        if self.modifier != 0 {
+            write!(f, "{:+}", self.modifier)?;
+        }
+        Ok(())
+    }
+}
+
+///The game's single source of randomness, kept on the GameState
+pub struct RngService {
+    seed: u64,
+    streams: HashMap<RngStream, Rng>,
+}
+impl RngService {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            streams: HashMap::new(),
+        }
+    }
+    ///picks a seed from the clock for when one isn't given
+    pub fn seed_from_time() -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|time| time.as_nanos() as u64)
+            .unwrap_or(0)
+    }
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    ///returns the generator for a stream, starting it up from the seed the first time it's used
+    pub fn stream(&mut self, stream: RngStream) -> &mut Rng {
+        let seed = self.seed;
+        self.streams.entry(stream).or_insert_with(|| {
+            //run the mixed seed through the generator once so streams with similar salts don't start out similar
+            Rng::new(Rng::new(seed ^ stream.salt()).next_u64())
+        })
+    }
+    pub fn roll(&mut self, stream: RngStream, dice: &Dice) -> i32 {
+        dice.roll(self.stream(stream))
+    }
+    pub fn range(&mut self, stream: RngStream, low: i32, high: i32) -> i32 {
+        self.stream(stream).range(low, high)
+    }
+    ///rolls a number from 0 to 99 for checking against a percentage chance
+    pub fn percentile(&mut self, stream: RngStream) -> i32 {
+        self.range(stream, 0, 99)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_dice_notation() {
+        assert_eq!(Dice::parse("2d6+1"), Ok(Dice::new(2, 6, 1)));
+        assert_eq!(Dice::parse("d20"), Ok(Dice::new(1, 20, 0)));
+        assert_eq!(Dice::parse(" 3D4 - 2 "), Ok(Dice::new(3, 4, -2)));
+        assert_eq!(Dice::parse("5"), Ok(Dice::flat(5)));
+        assert!(Dice::parse("0d6").is_err());
+        assert!(Dice::parse("2d").is_err());
+        assert!(Dice::parse("lots").is_err());
+    }
+
+    #[test]
+    fn writes_dice_back_out_the_same_way() {
+        for notation in ["2d6+1", "1d20", "3d4-2", "5"] {
+            assert_eq!(Dice::parse(notation).unwrap().to_string(), notation);
+        }
+    }
+
+    #[test]
+    fn the_same_seed_rolls_the_same_numbers() {
+        let dice = Dice::new(2, 6, 1);
+        let mut first = RngService::new(42);
+        let mut second = RngService::new(42);
+        for _ in 0..20 {
+            let roll = first.roll(RngStream::Damage, &dice);
+            assert!((3..=13).contains(&roll));
+            assert_eq!(roll, second.roll(RngStream::Damage, &dice));
+        }
+    }
+
+    #[test]
+    fn streams_dont_affect_each_other() {
+        let mut quiet = RngService::new(7);
+        let mut busy = RngService::new(7);
+        //rolling initiative on one of them shouldn't change what attacks roll
+        for _ in 0..5 {
+            busy.percentile(RngStream::Initiative);
+        }
+        for _ in 0..20 {
+            assert_eq!(
+                quiet.percentile(RngStream::Attack),
+                busy.percentile(RngStream::Attack)
+            );
+        }
+    }
+}
@@ -0,0 +1,118 @@
+/*
+This module holds the history of everything that's been reported to the player through the game log,
+stamped with when it happened so it can be scrolled back through in the UI and exported after an encounter
+*/
+use macroquad::prelude::*;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::Write;
+
+///The most messages the game log will remember before it starts throwing away the oldest ones
+pub const MAX_LOG_ENTRIES: usize = 500;
+
+///What kind of event a game log message is describing, used for colouring and filtering the log panel
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum LogCategory {
+    Movement,
+    Combat,
+    Turn,
+    System,
+}
+impl LogCategory {
+    ///short label that's printed next to the message in the panel and exported log
+    pub fn label(&self) -> &'static str {
+        match self {
+            LogCategory::Movement => "Move",
+            LogCategory::Combat => "Combat",
+            LogCategory::Turn => "Turn",
+            LogCategory::System => "System",
+        }
+    }
+    ///colour the message is drawn with in the log panel
+    pub fn color(&self) -> Color {
+        match self {
+            LogCategory::Movement => SKYBLUE,
+            LogCategory::Combat => ORANGE,
+            LogCategory::Turn => GOLD,
+            LogCategory::System => LIGHTGRAY,
+        }
+    }
+}
+
+///A single message in the game log along with the round and turn it happened on
+#[derive(Clone, Debug)]
+pub struct LogEntry {
+    pub round: i32,
+    pub turn: i32,
+    pub category: LogCategory,
+    pub text: String,
+}
+impl LogEntry {
+    ///the round/turn stamp that's shown in front of the message
+    pub fn stamp(&self) -> String {
+        format!("R{} T{}", self.round, self.turn)
+    }
+}
+
+///Bounded history of game log messages. Keeps track of how far the player has scrolled back
+///so the log panel can be rendered without any extra ui state
+pub struct GameLog {
+    entries: VecDeque<LogEntry>,
+    capacity: usize,
+    ///how many entries back from the newest one the panel is scrolled. 0 means it's following the newest messages
+    scroll: usize,
+}
+impl GameLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            scroll: 0,
+        }
+    }
+    ///Adds a message to the log, dropping the oldest one if the log is full. If the player has scrolled back
+    ///the view is nudged along with it so what they're reading doesn't move out from under them
+    pub fn push(&mut self, entry: LogEntry) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+        if self.scroll > 0 {
+            self.scroll_up(1);
+        }
+    }
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.scroll = (self.scroll + lines).min(self.entries.len().saturating_sub(1));
+    }
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_sub(lines);
+    }
+    ///jumps back to following the newest messages
+    pub fn scroll_to_bottom(&mut self) {
+        self.scroll = 0;
+    }
+    pub fn scroll(&self) -> usize {
+        self.scroll
+    }
+    ///Returns the entries that fit in a panel that can show `count` of them, taking the scroll into account.
+    ///They're returned oldest first so they can be drawn top to bottom (or reversed to draw bottom up)
+    pub fn visible(&self, count: usize) -> impl DoubleEndedIterator<Item = &LogEntry> {
+        let end = self.entries.len() - self.scroll.min(self.entries.len());
+        let start = end.saturating_sub(count);
+        self.entries.range(start..end)
+    }
+    ///Writes the whole log out to a text file, one stamped message per line
+    pub fn export(&self, path: &str) -> std::io::Result<()> {
+        let mut file = File::create(path)?;
+        for entry in self.entries.iter() {
+            writeln!(
+                file,
+                "[{}] [{}] {}",
+                entry.stamp(),
+                entry.category.label(),
+                entry.text
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -1,49 +1,174 @@
+use crate::ability_registry::*;
+use crate::action_economy::*;
+use crate::assets::*;
+use crate::audio::*;
 use crate::combat_action_type::*;
-use crate::init_ecs::*;
+use crate::components::ActionPoints;
+use crate::config::*;
+use crate::debug_log::*;
+use crate::dice::*;
+use crate::game_log::*;
+use crate::hot_reload::*;
 use crate::map::*;
-use crate::prelude::*;
+use crate::occupancy::*;
+use crate::replay::*;
 use crate::sound_atlas::*;
 use crate::texture_atlas::*;
 use hecs::*;
-use macroquad::prelude::*;
 
 pub struct GameState {
+    pub config: Config,
+    pub debug_log: DebugLogger,
+    pub manifest: AssetManifest,
+    ///what every action costs, read from the actions file
+    pub actions: ActionEconomy,
+    ///every ability a combatant can be given, read from the abilities file
+    pub abilities: AbilityRegistry,
+    ///only set up when hot reloading is switched on
+    pub asset_watcher: Option<AssetWatcher>,
     pub texture_atlas: TextureAtlas,
     pub sound_atlas: SoundAtlas,
+    pub audio: AudioPlayer,
     pub ecs: World,
-    pub turn_state: TurnState,
-    pub control_state: CombatActionType,
+    pub rng: RngService,
+    ///what the player is in the middle of doing this turn
+    pub control_state: ControlStateMachine,
     pub map: Map, //temporary just for testing combat.
-    pub log: Vec<String>,
+    ///who's standing on which tile, kept up to date as things move and go down
+    pub occupancy: OccupancyGrid,
+    pub log: GameLog,
     pub number_turns: i32,
     pub quitting: bool,
-    pub event_queue: Vec<GameEvent>, //ui_state: UiState,
+    ///every command given so far this run, saved when the player quits
+    pub replay: Replay,
+    ///set when a replay file is being played back instead of taking input
+    pub playback: Option<ReplayPlayer>,
+    ///which line of the gear panel is highlighted, only matters while it's open
+    pub gear_cursor: usize,
+    //ui_state: UiState,
 }
 
 impl GameState {
     pub async fn default() -> Self {
-        let log: Vec<String> = Vec::new();
-        let event_queue: Vec<GameEvent> = Vec::new();
-        Self {
-            texture_atlas: crate::texture_atlas::make().await,
-            sound_atlas: crate::sound_atlas::make().await,
-            ecs: crate::init_ecs::init_ecs(),
-            turn_state: TurnState::PlayerOne,
-            control_state: CombatActionType::None,
-            map: Map::new(),
+        let log = GameLog::new(MAX_LOG_ENTRIES);
+        let mut config = Config::from_args();
+        let mut debug_log = DebugLogger::new(&config);
+        //a replay has to be fought with the same seed, scenario and map it was recorded with to play out the same way
+        let mut playback: Option<ReplayPlayer> = None;
+        if let Some(path) = config.replay_path.clone() {
+            match Replay::load(&path) {
+                Ok(replay) => {
+                    config.seed = Some(replay.seed);
+                    if !replay.scenario.is_empty() {
+                        config.scenario = replay.scenario.clone();
+                    }
+                    if !replay.map_path.is_empty() {
+                        config.map_path = replay.map_path.clone();
+                    }
+                    debug_log.log(
+                        DebugLevel::Info,
+                        Subsystem::Input,
+                        &format!(
+                            "playing back {} commands from {}",
+                            replay.entries.len(),
+                            path
+                        ),
+                    );
+                    playback = Some(ReplayPlayer::new(&replay));
+                }
+                Err(error) => debug_log.log(DebugLevel::Error, Subsystem::Input, &error),
+            }
+        }
+        let audio = AudioPlayer::new(&config);
+        let (manifest, manifest_errors) = load_manifest().await;
+        for error in manifest_errors.iter() {
+            debug_log.log(DebugLevel::Error, Subsystem::Assets, error);
+        }
+        let (actions, action_errors) = load_action_economy().await;
+        for error in action_errors.iter() {
+            debug_log.log(DebugLevel::Error, Subsystem::Assets, error);
+        }
+        let (abilities, ability_errors) = load_ability_registry().await;
+        for error in ability_errors.iter() {
+            debug_log.log(DebugLevel::Error, Subsystem::Assets, error);
+        }
+        let texture_atlas = crate::texture_atlas::make(&manifest, &mut debug_log).await;
+        //headless runs don't load any sounds since there's nothing to play them on
+        let sound_atlas = if config.silent {
+            SoundAtlas::new()
+        } else {
+            crate::sound_atlas::make(&manifest, &mut debug_log).await
+        };
+        let map = match Map::load(&config.map_path).await {
+            Ok(map) => map,
+            Err(error) => {
+                debug_log.log(DebugLevel::Error, Subsystem::Assets, &error);
+                Map::new()
+            }
+        };
+        let rng = RngService::new(config.seed.unwrap_or_else(RngService::seed_from_time));
+        //the seed is always shown so any run can be played out again with --seed
+        debug_log.log(
+            DebugLevel::Info,
+            Subsystem::Combat,
+            &format!("random seed is {}", rng.seed()),
+        );
+        let ecs = match crate::init_ecs::init_ecs(&config.scenario) {
+            Some(ecs) => ecs,
+            None => {
+                debug_log.log(
+                    DebugLevel::Warn,
+                    Subsystem::Assets,
+                    &format!(
+                        "unknown scenario {}, using {} instead",
+                        config.scenario,
+                        crate::init_ecs::DEFAULT_SCENARIO
+                    ),
+                );
+                config.scenario = String::from(crate::init_ecs::DEFAULT_SCENARIO);
+                crate::init_ecs::init_ecs(&config.scenario).expect("default scenario is missing")
+            }
+        };
+        let occupancy = OccupancyGrid::build(&ecs);
+        let replay = Replay::new(rng.seed(), &config.scenario, &config.map_path);
+        let asset_watcher = if config.hot_reload {
+            Some(AssetWatcher::new(&manifest, &config.map_path))
+        } else {
+            None
+        };
+        let mut state = Self {
+            manifest,
+            actions,
+            abilities,
+            asset_watcher,
+            config,
+            debug_log,
+            texture_atlas,
+            sound_atlas,
+            audio,
+            ecs,
+            rng,
+            control_state: ControlStateMachine::new(),
+            map,
+            occupancy,
             log,
             number_turns: 0,
             quitting: false,
-            event_queue,
+            replay,
+            playback,
+            gear_cursor: 0,
+        };
+        crate::lib::systems::roll_initiative(&mut state);
+        //everyone starts the encounter with a full pool worked out from the actions file
+        let combatants: Vec<Entity> = state
+            .ecs
+            .query::<&ActionPoints>()
+            .iter()
+            .map(|(id, _ap)| id)
+            .collect();
+        for entity in combatants {
+            crate::lib::systems::refresh_action_points(&mut state, entity);
         }
+        state
     }
 }
-
-#[derive(Clone, Debug, PartialEq)]
-pub enum TurnState {
-    PlayerOne,
-    PlayerTwo,
-}
-pub enum GameEvent {
-    EntityMoved,
-}
@@ -0,0 +1,187 @@
+/*
+This module watches the files the game loads out of the resources folder and reloads any that change while the game
+is running, so textures, sounds, actions, abilities and the map can be iterated on without restarting. It polls
+modification times rather than using file system events since that's plenty fast enough for something that's only
+used in development
+*/
+use crate::ability_registry::*;
+use crate::action_economy::*;
+use crate::assets::*;
+use crate::debug_log::*;
+use crate::gamestate::*;
+use crate::map::*;
+use macroquad::prelude::*;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::SystemTime;
+
+///how many seconds to wait between checking the files for changes
+const POLL_INTERVAL: f64 = 1.0;
+
+///Remembers when each watched file was last modified so changes can be spotted
+pub struct AssetWatcher {
+    modified: HashMap<String, SystemTime>,
+    last_poll: f64,
+}
+impl AssetWatcher {
+    pub fn new(manifest: &AssetManifest, map_path: &str) -> Self {
+        let mut watcher = Self {
+            modified: HashMap::new(),
+            last_poll: get_time(),
+        };
+        //take a snapshot of how everything is right now so none of it counts as changed on the first poll
+        watcher.changed(manifest, map_path);
+        watcher
+    }
+    ///Returns every watched file that's been modified since the last time this was called. Files that appear for the
+    ///first time (eg. newly added to the manifest) only count as changed if they were already being watched
+    pub fn changed(&mut self, manifest: &AssetManifest, map_path: &str) -> Vec<String> {
+        let mut changed: Vec<String> = Vec::new();
+        for path in watched_paths(manifest, map_path) {
+            let Some(modified) = modified_time(&path) else {
+                continue;
+            };
+            match self.modified.insert(path.clone(), modified) {
+                Some(previous) if previous != modified => changed.push(path),
+                _ => {}
+            }
+        }
+        changed
+    }
+}
+
+///every file the game has loaded out of the resources folder, with no duplicates
+fn watched_paths(manifest: &AssetManifest, map_path: &str) -> Vec<String> {
+    let mut paths: Vec<String> = vec![
+        String::from(MANIFEST_PATH),
+        String::from(ACTIONS_PATH),
+        String::from(ABILITIES_PATH),
+        map_path.to_string(),
+    ];
+    for entry in manifest.entries.iter() {
+        if !paths.contains(&entry.path) {
+            paths.push(entry.path.clone());
+        }
+    }
+    paths
+}
+
+fn modified_time(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(Path::new("resources").join(path))
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+///Checks the watched files every so often and reloads whatever has changed. Only the assets and map are touched,
+///the ECS and everything else about the combat carries on as it was
+pub async fn poll(state: &mut GameState) {
+    let Some(mut watcher) = state.asset_watcher.take() else {
+        return;
+    };
+    if get_time() - watcher.last_poll >= POLL_INTERVAL {
+        watcher.last_poll = get_time();
+        for path in watcher.changed(&state.manifest, &state.config.map_path) {
+            if path == MANIFEST_PATH {
+                reload_manifest(state).await;
+            } else if path == ACTIONS_PATH {
+                reload_actions(state).await;
+            } else if path == ABILITIES_PATH {
+                reload_abilities(state).await;
+            } else if path == state.config.map_path {
+                reload_map(state).await;
+            } else {
+                let manifest = state.manifest.clone();
+                if manifest.textures().any(|entry| entry.path == path) {
+                    crate::texture_atlas::reload_texture(
+                        &mut state.texture_atlas,
+                        &manifest,
+                        &path,
+                        &mut state.debug_log,
+                    )
+                    .await;
+                }
+                if !state.config.silent && manifest.sounds().any(|entry| entry.path == path) {
+                    crate::sound_atlas::reload_sound(
+                        &mut state.sound_atlas,
+                        &manifest,
+                        &path,
+                        &mut state.debug_log,
+                    )
+                    .await;
+                }
+            }
+        }
+    }
+    state.asset_watcher = Some(watcher);
+}
+
+///The manifest itself changed so any key could now point anywhere, the simplest thing is to load everything again
+async fn reload_manifest(state: &mut GameState) {
+    let (manifest, errors) = load_manifest().await;
+    for error in errors.iter() {
+        state
+            .debug_log
+            .log(DebugLevel::Error, Subsystem::Assets, error);
+    }
+    state.texture_atlas = crate::texture_atlas::make(&manifest, &mut state.debug_log).await;
+    if !state.config.silent {
+        state.sound_atlas = crate::sound_atlas::make(&manifest, &mut state.debug_log).await;
+    }
+    state.manifest = manifest;
+    state.debug_log.log(
+        DebugLevel::Info,
+        Subsystem::Assets,
+        &format!("reloaded {}", MANIFEST_PATH),
+    );
+}
+
+///Swaps in the new costs. They're used from the next action on, nobody's AP is touched until the next round
+async fn reload_actions(state: &mut GameState) {
+    let (actions, errors) = load_action_economy().await;
+    for error in errors.iter() {
+        state
+            .debug_log
+            .log(DebugLevel::Error, Subsystem::Assets, error);
+    }
+    state.actions = actions;
+    state.debug_log.log(
+        DebugLevel::Info,
+        Subsystem::Assets,
+        &format!("reloaded {}", ACTIONS_PATH),
+    );
+}
+
+///Swaps in the new abilities. Combatants only hold ability keys so they pick up the changes straight away, and
+///anything that's been removed from the file just stops showing up on their action bar
+async fn reload_abilities(state: &mut GameState) {
+    let (abilities, errors) = load_ability_registry().await;
+    for error in errors.iter() {
+        state
+            .debug_log
+            .log(DebugLevel::Error, Subsystem::Assets, error);
+    }
+    state.abilities = abilities;
+    state.debug_log.log(
+        DebugLevel::Info,
+        Subsystem::Assets,
+        &format!("reloaded {}", ABILITIES_PATH),
+    );
+}
+
+///Swaps in the new version of the map. If it can't be read the old one is kept so a half saved file doesn't
+///wipe out the encounter
+async fn reload_map(state: &mut GameState) {
+    match Map::load(&state.config.map_path).await {
+        Ok(map) => {
+            state.map = map;
+            state.debug_log.log(
+                DebugLevel::Info,
+                Subsystem::Assets,
+                &format!("reloaded map {}", state.config.map_path),
+            );
+        }
+        Err(error) => state
+            .debug_log
+            .log(DebugLevel::Error, Subsystem::Assets, &error),
+    }
+}
@@ -1,31 +1,247 @@
+use crate::lib::inventory::*;
 use crate::prelude::*;
 use hecs::*;
 use macroquad::prelude::*;
 
-///RN just have this setup for a basic fight but in the future this can be passed an identifier for deciding which
-///scene to launch in the combat sandbox.
-pub fn init_ecs() -> World {
+///scenario the sandbox launches into if one isn't picked with --scenario
+pub const DEFAULT_SCENARIO: &str = "sandbox";
+
+///Builds the world for the named scenario, or None if there isn't one by that name
+pub fn init_ecs(scenario: &str) -> Option<World> {
+    match scenario {
+        "sandbox" => Some(sandbox()),
+        _ => None,
+    }
+}
+
+///RN just have this setup for a basic fight but more scenarios can be added next to it and picked in init_ecs
+fn sandbox() -> World {
     //first add the decorative entities (furniture, cover, whatever)
     //then spawn in the various characters in the combat scene
     //then define the combat scene
     //that should be everything??
     let mut ecs = World::new();
+    //gear is made up of item entities that characters point to from their equipment and inventory, or that sit on
+    //a tile waiting to be picked up
+    let rifle = ecs.spawn((
+        Name::new(
+            String::from("Service Rifle"),
+            String::from("A long barrelled rifle built for reaching out across the field."),
+        ),
+        Item,
+        RangedWeapon::new(12, 70, Dice::parse("1d6+2").unwrap()).with_fire_modes(&[
+            FireMode::Single,
+            FireMode::Burst,
+            FireMode::Auto,
+        ]),
+        Magazine::new(10, 20),
+        SelectedFireMode(FireMode::Single),
+    ));
+    let pistol = ecs.spawn((
+        Name::new(
+            String::from("Scrap Pistol"),
+            String::from("A pistol cobbled together from salvaged parts."),
+        ),
+        Item,
+        RangedWeapon::new(8, 65, Dice::parse("1d4+1").unwrap()),
+        Magazine::new(6, 12),
+        SelectedFireMode(FireMode::Single),
+    ));
+    let knife = ecs.spawn((
+        Name::new(
+            String::from("Combat Knife"),
+            String::from("A heavy bladed knife kept sharp for close quarters."),
+        ),
+        Item,
+        MeleeWeapon::new(75, Dice::new(1, 4, 1)),
+    ));
+    let club = ecs.spawn((
+        Name::new(
+            String::from("Rebar Club"),
+            String::from(
+                "A length of rusted rebar with a lump of concrete still stuck to one end.",
+            ),
+        ),
+        Item,
+        MeleeWeapon::new(65, Dice::new(1, 6, 0)),
+    ));
+    //throwables are used up when they're thrown so everyone gets their own
+    let frag = ecs.spawn((
+        Name::new(
+            String::from("Frag Grenade"),
+            String::from("A standard issue fragmentation grenade that'll flatten light cover."),
+        ),
+        Item,
+        Throwable {
+            range: 8,
+            accuracy: 80,
+            template: BlastTemplate::Circle { radius: 2 },
+            damage: Dice::new(2, 6, 0),
+            falloff: 2,
+            lingering: None,
+            destroys_cover: true,
+        },
+    ));
+    let molotov = ecs.spawn((
+        Name::new(
+            String::from("Molotov"),
+            String::from("A bottle of fuel with a burning rag stuffed in the neck."),
+        ),
+        Item,
+        Throwable {
+            range: 6,
+            accuracy: 70,
+            template: BlastTemplate::Cone { length: 2 },
+            damage: Dice::new(1, 4, 0),
+            falloff: 1,
+            lingering: Some((HazardKind::Fire, 2)),
+            destroys_cover: false,
+        },
+    ));
+    let vest = ecs.spawn((
+        Name::new(
+            String::from("Flak Vest"),
+            String::from("A padded vest that takes the sting out of most hits."),
+        ),
+        Item,
+        Armour { soak: 1 },
+    ));
+    //how far they move and how much they can do each turn comes from their attributes
+    let pc_attributes = Attributes::new(5, 5, 5, 6);
     let pc_01 = ecs.spawn((
-        Renderable::new(String::from("character")),
+        Name::new(
+            String::from("Vasquez"),
+            String::from("A steady-handed mercenary in patched fatigues."),
+        ),
+        Renderable::with_layers(String::from("character"), vec![String::from("rifle")]),
+        AnimatedSprite::new(),
         IVec2::new(0, 0),
-        ActionPoints::new(),
-        MovementPoints::default(),
+        VisualPosition::at(IVec2::new(0, 0)),
+        Facing::Right,
+        ActionPoints::with_max(pc_attributes.action_points()),
+        MovementPoints::new(pc_attributes.movement_allowance()),
+        MoveHistory::default(),
+        Health::new(10),
+        StatusEffects::default(),
         ControlType::PC,
         Collideable,
     ));
+    //hecs only takes so many components in one go, so what they're carrying is added separately
+    ecs.insert(
+        pc_01,
+        (
+            Equipment::default()
+                .with(EquipmentSlot::Primary, rifle)
+                .with(EquipmentSlot::Sidearm, knife)
+                .with(EquipmentSlot::Armour, vest)
+                .with(EquipmentSlot::Utility, frag),
+            Inventory::default(),
+            pc_attributes,
+            Skills::default()
+                .with(Skill::Marksmanship, 1)
+                .with(Skill::Melee, 1)
+                .with(Skill::Resolve, 1),
+            //abilities are looked up by key in the ability registry
+            Abilities::new(&["hunker", "first_aid", "smoke_screen"]),
+            StatusTimers::default(),
+        ),
+    )
+    .expect("PC was just spawned");
+    let npc_attributes = Attributes::new(6, 6, 4, 4);
     let npc_01 = ecs.spawn((
+        Name::new(
+            String::from("Raider"),
+            String::from("A scavenger armed with whatever they could pry loose."),
+        ),
         Renderable::new(String::from("character")),
+        AnimatedSprite::new(),
         IVec2::new(20, 20),
-        ActionPoints::new(),
-        MovementPoints::default(),
+        VisualPosition::at(IVec2::new(20, 20)),
+        Facing::Left,
+        ActionPoints::with_max(npc_attributes.action_points()),
+        MovementPoints::new(npc_attributes.movement_allowance()),
+        MoveHistory::default(),
+        Health::new(10),
+        StatusEffects::default(),
         ControlType::NPC,
         Collideable,
     ));
+    ecs.insert(
+        npc_01,
+        (
+            Equipment::default()
+                .with(EquipmentSlot::Primary, pistol)
+                .with(EquipmentSlot::Sidearm, club)
+                .with(EquipmentSlot::Utility, molotov),
+            Inventory::default(),
+            npc_attributes,
+            Skills::default()
+                .with(Skill::Melee, 1)
+                .with(Skill::Athletics, 1),
+            Abilities::new(&["hunker", "concussive_blow"]),
+            StatusTimers::default(),
+        ),
+    )
+    .expect("NPC was just spawned");
+    refresh_equipped(&mut ecs, pc_01);
+    refresh_equipped(&mut ecs, npc_01);
+    //some spare gear lying around to be picked up
+    ecs.spawn((
+        Name::new(
+            String::from("Service Pistol"),
+            String::from("A reliable sidearm someone left behind in a hurry."),
+        ),
+        Item,
+        IVec2::new(6, 2),
+        RangedWeapon::new(8, 70, Dice::new(1, 4, 1)),
+        Magazine::new(8, 8),
+        SelectedFireMode(FireMode::Single),
+    ));
+    ecs.spawn((
+        Name::new(
+            String::from("Smoke Grenade"),
+            String::from(
+                "Fills the area with a thick screen of smoke that's hard to shoot through.",
+            ),
+        ),
+        Item,
+        IVec2::new(10, 12),
+        Throwable {
+            range: 8,
+            accuracy: 85,
+            template: BlastTemplate::Circle { radius: 2 },
+            damage: Dice::flat(0),
+            falloff: 0,
+            lingering: Some((HazardKind::Smoke, 3)),
+            destroys_cover: false,
+        },
+    ));
+    //a few crates to take cover behind
+    for pos in [IVec2::new(4, 3), IVec2::new(17, 17), IVec2::new(24, 9)] {
+        ecs.spawn((
+            Name::new(
+                String::from("Crate"),
+                String::from("A sturdy supply crate, good for ducking behind."),
+            ),
+            Renderable::new(String::from("half cover")),
+            pos,
+            ZOrder::on(RenderLayer::Props),
+            Cover(CoverLevel::Half),
+            Collideable,
+        ));
+    }
+    //and a rusted old barrel that barely hides anything
+    ecs.spawn((
+        Name::new(
+            String::from("Barrel"),
+            String::from("A rusted oil drum. Better than nothing, but not by much."),
+        ),
+        Renderable::new(String::from("quarter cover")),
+        IVec2::new(12, 14),
+        ZOrder::on(RenderLayer::Props),
+        Cover(CoverLevel::Quarter),
+        Collideable,
+    ));
     ecs.spawn((CombatEncounter::new(vec![pc_01, npc_01]),));
     ecs
 }
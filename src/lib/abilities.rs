@@ -0,0 +1,82 @@
+/*
+This module contains the helpers for using abilities: looking up what's in a combatant's action bar, checking whether
+an ability can be used on a tile and working out which tiles it reaches
+*/
+use super::blast::*;
+use super::geometry::*;
+use super::targeting::*;
+use crate::ability_registry::*;
+use crate::prelude::*;
+use hecs::*;
+use macroquad::prelude::*;
+
+///returns the ability in a slot of an entity's action bar, if there's one there that's in the registry
+pub fn ability_in_slot(state: &GameState, entity: Entity, slot: usize) -> Option<AbilityDef> {
+    let abilities = state.ecs.get::<&Abilities>(entity).ok()?;
+    let key = abilities.slot(slot)?;
+    state.abilities.get(key).cloned()
+}
+
+///every ability on an entity's action bar along with the rounds left on its cooldown, skipping any that aren't in
+///the registry
+pub fn action_bar(state: &GameState, entity: Entity) -> Vec<(usize, AbilityDef, i32)> {
+    let Ok(abilities) = state.ecs.get::<&Abilities>(entity) else {
+        return Vec::new();
+    };
+    abilities
+        .known()
+        .iter()
+        .enumerate()
+        .filter_map(|(slot, key)| {
+            state
+                .abilities
+                .get(key)
+                .map(|ability| (slot, ability.clone(), abilities.cooldown(key)))
+        })
+        .collect()
+}
+
+///Checks whether an ability could be aimed at a tile, which means it being in range and in sight and, for abilities
+///aimed at someone, there being someone there. Cost and cooldown aren't checked here
+pub fn target_problem(
+    state: &GameState,
+    user: Entity,
+    ability: &AbilityDef,
+    target: IVec2,
+) -> Option<String> {
+    let Ok(pos) = state.ecs.get::<&IVec2>(user).map(|pos| *pos) else {
+        return Some(String::from("they aren't on the map"));
+    };
+    if ability.targeting == AbilityTargeting::SelfOnly {
+        return None;
+    }
+    if distance(pos, target) > ability.range {
+        Some(String::from("it's out of range"))
+    } else if landing_tile(state, pos, target) != target {
+        Some(String::from("they can't see there"))
+    } else if ability.targeting == AbilityTargeting::Entity && combatant_at(state, target).is_none()
+    {
+        Some(String::from("there's nobody there"))
+    } else {
+        None
+    }
+}
+
+///Returns every tile an ability used on a target reaches
+pub fn ability_tiles(
+    state: &GameState,
+    user: Entity,
+    ability: &AbilityDef,
+    target: IVec2,
+) -> Vec<IVec2> {
+    let Ok(pos) = state.ecs.get::<&IVec2>(user).map(|pos| *pos) else {
+        return Vec::new();
+    };
+    match ability.targeting {
+        AbilityTargeting::SelfOnly => vec![pos],
+        AbilityTargeting::Tile | AbilityTargeting::Entity => vec![target],
+        AbilityTargeting::Area { radius } => {
+            blast_tiles(state, BlastTemplate::Circle { radius }, pos, target)
+        }
+    }
+}
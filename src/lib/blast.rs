@@ -0,0 +1,156 @@
+/*
+This module contains the helpers for throwing explosives: where a throw ends up, which tiles the blast reaches and
+how much damage gets through to whoever is standing on them
+*/
+use super::geometry::*;
+use super::hit_chance::*;
+use crate::prelude::*;
+use hecs::*;
+use macroquad::prelude::*;
+
+///how much harder a throw is to land for every tile it has to travel
+pub const THROW_PENALTY_PER_TILE: i32 = 3;
+///how wide the cone of a cone shaped blast spreads
+pub const BLAST_CONE_DEGREES: f32 = 90.0;
+
+///returns the throwable an entity has ready, along with the item entity itself so it can be used up
+pub fn equipped_throwable(ecs: &World, entity: Entity) -> Option<(Entity, Throwable)> {
+    let item = ecs.get::<&EquippedThrowable>(entity).ok()?.get();
+    ecs.get::<&Throwable>(item)
+        .ok()
+        .map(|throwable| (item, *throwable))
+}
+
+///chance out of 100 of a throw landing on the tile it was aimed at
+pub fn throw_chance(throwable: &Throwable, distance: i32) -> i32 {
+    (throwable.accuracy - distance * THROW_PENALTY_PER_TILE).clamp(MIN_HIT_CHANCE, MAX_HIT_CHANCE)
+}
+
+///how many tiles a missed throw can end up from where it was aimed
+pub fn scatter_distance(distance: i32) -> i32 {
+    1 + distance / 4
+}
+
+///Follows a throw from one tile to another and returns where it ends up. Anything thrown at a wall bounces off it
+///and drops on the last open tile before it
+pub fn landing_tile(state: &GameState, from: IVec2, to: IVec2) -> IVec2 {
+    let mut landing = from;
+    for tile in line(from, to) {
+        if !state.map.can_see_through(tile) {
+            break;
+        }
+        landing = tile;
+    }
+    landing
+}
+
+///Returns every tile the template covers when it goes off at the centre, thrown from the origin. Nothing is checked
+///against the map here, that's left to `blast_tiles`
+pub fn template_tiles(template: BlastTemplate, origin: IVec2, centre: IVec2) -> Vec<IVec2> {
+    let size = match template {
+        BlastTemplate::Circle { radius } => radius,
+        BlastTemplate::Cone { length } => length,
+    };
+    let direction = (centre - origin).as_vec2().normalize_or_zero();
+    let half_angle = (BLAST_CONE_DEGREES / 2.0).to_radians();
+    let mut tiles: Vec<IVec2> = Vec::new();
+    for y in -size..=size {
+        for x in -size..=size {
+            let offset = IVec2::new(x, y);
+            let inside = match template {
+                BlastTemplate::Circle { .. } => true,
+                //a cone with no direction to point in just goes off all round
+                BlastTemplate::Cone { .. } => {
+                    offset == IVec2::ZERO
+                        || direction == Vec2::ZERO
+                        || direction.dot(offset.as_vec2().normalize())
+                            >= half_angle.cos() - f32::EPSILON
+                }
+            };
+            if inside {
+                tiles.push(centre + offset);
+            }
+        }
+    }
+    tiles
+}
+
+///Returns the tiles an explosion actually reaches, which is the template minus anything off the map or hidden from
+///the centre of the blast behind a wall
+pub fn blast_tiles(
+    state: &GameState,
+    template: BlastTemplate,
+    origin: IVec2,
+    centre: IVec2,
+) -> Vec<IVec2> {
+    template_tiles(template, origin, centre)
+        .into_iter()
+        .filter(|tile| {
+            state.map.can_see_through(*tile)
+                && line(centre, *tile)
+                    .iter()
+                    .all(|step| state.map.can_see_through(*step))
+        })
+        .collect()
+}
+
+///Works out how much of a blast's damage reaches a tile, taking off the falloff for how far it is from the centre
+///and whatever the cover between them soaks up
+pub fn blast_damage(
+    state: &GameState,
+    throwable: &Throwable,
+    centre: IVec2,
+    tile: IVec2,
+    rolled: i32,
+) -> (i32, CoverLevel) {
+    let after_falloff = (rolled - throwable.falloff * distance(centre, tile)).max(0);
+    let cover = cover_against(state, centre, tile);
+    (after_falloff * (100 - cover.blast_reduction()) / 100, cover)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn circles_cover_every_tile_within_the_radius() {
+        let tiles = template_tiles(
+            BlastTemplate::Circle { radius: 1 },
+            IVec2::new(0, 0),
+            IVec2::new(5, 5),
+        );
+        assert_eq!(tiles.len(), 9);
+        assert!(tiles.contains(&IVec2::new(4, 4)));
+        assert!(tiles.contains(&IVec2::new(6, 6)));
+    }
+
+    #[test]
+    fn cones_carry_on_the_way_they_were_thrown() {
+        let tiles = template_tiles(
+            BlastTemplate::Cone { length: 2 },
+            IVec2::new(0, 5),
+            IVec2::new(5, 5),
+        );
+        assert!(tiles.contains(&IVec2::new(5, 5)));
+        assert!(tiles.contains(&IVec2::new(7, 5)));
+        assert!(!tiles.contains(&IVec2::new(3, 5)));
+        assert!(!tiles.contains(&IVec2::new(5, 7)));
+    }
+
+    #[test]
+    fn longer_throws_are_less_accurate() {
+        assert_eq!(scatter_distance(2), 1);
+        assert_eq!(scatter_distance(8), 3);
+        let throwable = Throwable {
+            range: 8,
+            accuracy: 80,
+            template: BlastTemplate::Circle { radius: 1 },
+            damage: Dice::new(2, 6, 0),
+            falloff: 2,
+            lingering: None,
+            destroys_cover: true,
+        };
+        assert!(throw_chance(&throwable, 1) > throw_chance(&throwable, 6));
+        assert_eq!(throw_chance(&throwable, 100), MIN_HIT_CHANCE);
+    }
+}
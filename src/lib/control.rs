@@ -0,0 +1,112 @@
+use crate::audio::sounds;
+use crate::combat_action_type::*;
+use crate::lib::format::*;
+use crate::lib::targeting::*;
+use crate::prelude::*;
+use hecs::*;
+use macroquad::prelude::*;
+
+///Puts the player into a control state and runs whatever needs setting up for it. The cost is what was paid to get
+///in, which is handed back if they back out before doing anything
+pub fn enter_state(
+    state: &mut GameState,
+    active_entity: Entity,
+    to: CombatActionType,
+    paid: Option<ActionCost>,
+) -> Result<(), String> {
+    let change = state.control_state.enter(to, paid)?;
+    on_enter(state, active_entity, change);
+    Ok(())
+}
+
+///The player did what the current state was for, so it's back to picking an action
+pub fn complete_state(state: &mut GameState, active_entity: Entity) {
+    if let Some(change) = state.control_state.complete() {
+        on_exit(state, active_entity, change);
+    }
+}
+
+///The player backed out of the current state, anything they paid to get into it comes back if nothing happened
+pub fn cancel_state(
+    state: &mut GameState,
+    active_entity: Entity,
+    action_points: &mut ActionPoints,
+) {
+    let Some(change) = state.control_state.cancel() else {
+        return;
+    };
+    state
+        .ecs
+        .spawn((SoundEffect::new(SoundCategory::Ui, sounds::UI_CANCEL),));
+    if let Some(refund) = change
+        .refund
+        .filter(|refund| refund.kind != ActionKind::Free)
+    {
+        action_points.refund(refund);
+        let message = match refund.kind {
+            ActionKind::Bonus => "{actor} changes their mind and gets their bonus action back.",
+            _ => "{actor} changes their mind and gets {ap} AP back.",
+        };
+        state.ecs.spawn((GameLogMessage::new(
+            LogCategory::Movement,
+            format_message(
+                message,
+                &[
+                    ("actor", entity_name(&state.ecs, active_entity)),
+                    ("ap", refund.ap.to_string()),
+                ],
+            ),
+        ),));
+    }
+    on_exit(state, active_entity, change);
+}
+
+///Something the player didn't choose ended the current state, like being gunned down mid move
+pub fn interrupt_state(state: &mut GameState, active_entity: Entity) {
+    if let Some(change) = state.control_state.interrupt() {
+        on_exit(state, active_entity, change);
+    }
+}
+
+///sets up anything a state needs while the player is in it
+fn on_enter(state: &mut GameState, active_entity: Entity, change: StateChange) {
+    state.ecs.spawn((DebugLogMessage::new(
+        DebugLevel::Trace,
+        Subsystem::Input,
+        format!("entering {:?} from {:?}", change.to, change.from),
+    ),));
+    if change.to.uses_reticule() {
+        //the reticule starts on top of whoever is aiming it
+        if let Ok(pos) = state.ecs.get::<&IVec2>(active_entity).map(|pos| *pos) {
+            crate::lib::systems::spawn_entity(
+                state,
+                (
+                    Reticule::new(active_entity),
+                    pos,
+                    ZOrder::on(RenderLayer::UiOverlay),
+                ),
+            );
+        }
+    }
+    if change.to == CombatActionType::Inventory {
+        state.gear_cursor = 0;
+    }
+}
+
+///cleans up after a state once the player has left it, however that happened
+fn on_exit(state: &mut GameState, active_entity: Entity, change: StateChange) {
+    state.ecs.spawn((DebugLogMessage::new(
+        DebugLevel::Trace,
+        Subsystem::Input,
+        format!("leaving {:?} ({:?})", change.from, change.reason),
+    ),));
+    if change.from.uses_reticule() {
+        if let Some((reticule, _pos)) = find_reticule(&state.ecs) {
+            crate::lib::systems::despawn_entity(state, reticule);
+        }
+    }
+    if change.from == CombatActionType::Movement {
+        //whatever movement points are left over from this move are gone, the next one starts fresh
+        crate::lib::systems::refresh_mp(state, active_entity);
+    }
+}
@@ -0,0 +1,87 @@
+/*
+This module contains the helpers for turning game state into text for the game log and HUD so every system
+describes entities and places the same way
+*/
+use crate::prelude::*;
+use hecs::*;
+use macroquad::prelude::*;
+
+///Returns the name of an entity for use in messages, or a stand in if it doesn't have one
+pub fn entity_name(ecs: &World, entity: Entity) -> String {
+    match ecs.get::<&Name>(entity) {
+        Ok(name) => name.get_name(),
+        Err(_) => String::from("Something"),
+    }
+}
+
+///describes a tile position the way it's shown to the player
+pub fn tile_name(pos: IVec2) -> String {
+    format!("({}, {})", pos.x, pos.y)
+}
+
+///Fills in a message template by replacing each `{key}` with its value, eg.
+///`format_message("{actor} moves to {tile}", &[("actor", name), ("tile", tile_name(pos))])`.
+///Keys that aren't given a value are left in the message as is so they're easy to spot.
+///The template is only read through once, so a value that happens to contain `{something}` is never filled in itself
+pub fn format_message(template: &str, values: &[(&str, String)]) -> String {
+    let mut message = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        message.push_str(&rest[..open]);
+        let after_open = &rest[open + 1..];
+        let Some(close) = after_open.find('}') else {
+            //no closing brace so there's nothing left to fill in
+            rest = &rest[open..];
+            break;
+        };
+        let key = &after_open[..close];
+        match values.iter().find(|(name, _value)| *name == key) {
+            Some((_name, value)) => message.push_str(value),
+            None => message.push_str(&rest[open..open + close + 2]),
+        }
+        rest = &after_open[close + 1..];
+    }
+    message.push_str(rest);
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_in_every_key() {
+        let message = format_message(
+            "{actor} moves to {tile}",
+            &[
+                ("actor", String::from("Ash")),
+                ("tile", String::from("(1, 2)")),
+            ],
+        );
+        assert_eq!(message, "Ash moves to (1, 2)");
+    }
+
+    #[test]
+    fn leaves_unknown_keys_alone() {
+        let message = format_message("{actor} hits {target}", &[("actor", String::from("Ash"))]);
+        assert_eq!(message, "Ash hits {target}");
+    }
+
+    #[test]
+    fn values_are_not_filled_in_again() {
+        let message = format_message(
+            "{actor} moves to {tile}",
+            &[
+                ("actor", String::from("Mr {tile}")),
+                ("tile", String::from("(1, 2)")),
+            ],
+        );
+        assert_eq!(message, "Mr {tile} moves to (1, 2)");
+    }
+
+    #[test]
+    fn unclosed_braces_are_kept() {
+        let message = format_message("{actor} shrugs {", &[("actor", String::from("Ash"))]);
+        assert_eq!(message, "Ash shrugs {");
+    }
+}
@@ -0,0 +1,41 @@
+/*
+This module contains the grid maths shared by movement, targeting and anything else that needs to reason about
+distances and lines between tiles
+*/
+use macroquad::prelude::*;
+
+///Distance between two tiles counting diagonal steps as one, which matches how far something can move since
+///every one of the eight directions costs the same
+pub fn distance(from: IVec2, to: IVec2) -> i32 {
+    (to.x - from.x).abs().max((to.y - from.y).abs())
+}
+
+///checks if two tiles are next to each other, including diagonally
+pub fn is_adjacent(a: IVec2, b: IVec2) -> bool {
+    a != b && distance(a, b) == 1
+}
+
+///Returns every tile a straight line from one tile to another passes through using Bresenham's algorithm.
+///The starting tile isn't included but the end tile is
+pub fn line(from: IVec2, to: IVec2) -> Vec<IVec2> {
+    let mut tiles: Vec<IVec2> = Vec::new();
+    let dx = (to.x - from.x).abs();
+    let dy = -(to.y - from.y).abs();
+    let step_x = if from.x < to.x { 1 } else { -1 };
+    let step_y = if from.y < to.y { 1 } else { -1 };
+    let mut error = dx + dy;
+    let mut current = from;
+    while current != to {
+        let doubled = error * 2;
+        if doubled >= dy {
+            error += dy;
+            current.x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            current.y += step_y;
+        }
+        tiles.push(current);
+    }
+    tiles
+}
@@ -0,0 +1,318 @@
+/*
+This module works out the chance of an attack hitting. The calculation itself is a pure function of the things that
+affect it so it's easy to reason about, and it hands back an itemised breakdown so the numbers can be shown to the
+player instead of just a final percentage
+*/
+use super::geometry::*;
+use super::skills::*;
+use super::targeting::*;
+use crate::prelude::*;
+use hecs::*;
+use macroquad::prelude::*;
+
+///no shot is ever a sure thing, or completely hopeless
+pub const MIN_HIT_CHANCE: i32 = 5;
+pub const MAX_HIT_CHANCE: i32 = 95;
+
+///How far away a target is relative to the range of the weapon shooting at it
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum RangeBand {
+    ///a melee attack on someone right next to the attacker, which isn't affected by range at all
+    Melee,
+    PointBlank,
+    Short,
+    Medium,
+    Long,
+    OutOfRange,
+}
+impl RangeBand {
+    pub fn from_distance(distance: i32, range: i32) -> Self {
+        if distance <= 1 {
+            RangeBand::PointBlank
+        } else if distance * 2 <= range {
+            RangeBand::Short
+        } else if distance * 4 <= range * 3 {
+            RangeBand::Medium
+        } else if distance <= range {
+            RangeBand::Long
+        } else {
+            RangeBand::OutOfRange
+        }
+    }
+    pub fn label(&self) -> &'static str {
+        match self {
+            RangeBand::Melee => "Melee",
+            RangeBand::PointBlank => "Point blank",
+            RangeBand::Short => "Short range",
+            RangeBand::Medium => "Medium range",
+            RangeBand::Long => "Long range",
+            RangeBand::OutOfRange => "Out of range",
+        }
+    }
+    pub fn modifier(&self) -> i32 {
+        match self {
+            RangeBand::Melee => 0,
+            RangeBand::PointBlank => 15,
+            RangeBand::Short => 0,
+            RangeBand::Medium => -10,
+            RangeBand::Long => -25,
+            RangeBand::OutOfRange => -100,
+        }
+    }
+}
+
+///Everything that goes into the chance of an attack hitting
+#[derive(Clone, PartialEq, Debug)]
+pub struct HitChanceInputs {
+    pub weapon_accuracy: i32,
+    pub range_band: RangeBand,
+    pub cover: CoverLevel,
+    pub target_status: Vec<StatusEffect>,
+    ///the attacker's own bonus to hit from their attributes and skill with the weapon
+    pub attacker_skill: i32,
+    ///the mode a ranged weapon is being fired in, melee attacks count as a single shot
+    pub fire_mode: FireMode,
+}
+
+///The final chance to hit, as a percentage, along with each thing that added to or took away from it
+#[derive(Clone, PartialEq, Debug)]
+pub struct HitChance {
+    pub probability: i32,
+    pub breakdown: Vec<(String, i32)>,
+}
+impl HitChance {
+    ///the breakdown written out on one line for the game log, eg. "Weapon accuracy 70, Long range -25, ..."
+    pub fn describe(&self) -> String {
+        self.breakdown
+            .iter()
+            .map(|(reason, amount)| {
+                if reason == "Weapon accuracy" {
+                    format!("{} {}", reason, amount)
+                } else {
+                    format!("{} {:+}", reason, amount)
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+///Works out the chance of an attack hitting. Starts from the weapon's accuracy and adds up every modifier,
+///then clamps the total so there's always some chance either way
+pub fn hit_chance(inputs: &HitChanceInputs) -> HitChance {
+    let mut breakdown: Vec<(String, i32)> =
+        vec![(String::from("Weapon accuracy"), inputs.weapon_accuracy)];
+    if inputs.attacker_skill != 0 {
+        breakdown.push((String::from("Attacker skill"), inputs.attacker_skill));
+    }
+    if inputs.fire_mode.modifier() != 0 {
+        breakdown.push((
+            String::from(inputs.fire_mode.label()),
+            inputs.fire_mode.modifier(),
+        ));
+    }
+    if inputs.range_band.modifier() != 0 {
+        breakdown.push((
+            String::from(inputs.range_band.label()),
+            inputs.range_band.modifier(),
+        ));
+    }
+    if inputs.cover.modifier() != 0 {
+        breakdown.push((String::from(inputs.cover.label()), inputs.cover.modifier()));
+    }
+    for status in inputs.target_status.iter() {
+        if status.defence_modifier() != 0 {
+            breakdown.push((
+                format!("Target {}", status.label()),
+                status.defence_modifier(),
+            ));
+        }
+    }
+    let total: i32 = breakdown.iter().map(|(_reason, amount)| amount).sum();
+    let probability = total.clamp(MIN_HIT_CHANCE, MAX_HIT_CHANCE);
+    if probability != total {
+        breakdown.push((String::from("Limit"), probability - total));
+    }
+    HitChance {
+        probability,
+        breakdown,
+    }
+}
+
+///Works out how much cover a target has against a shot coming from a tile. Cover counts if it's right next to the
+///target on the side the shot is coming from: walls count as full cover, low walls as half and props give whatever
+///cover they provide
+pub fn cover_against(state: &GameState, shooter_pos: IVec2, target_pos: IVec2) -> CoverLevel {
+    if is_adjacent(shooter_pos, target_pos) || shooter_pos == target_pos {
+        //there's nothing to hide behind from someone right next to you
+        return CoverLevel::None;
+    }
+    //the last tile the shot passes through before reaching the target
+    let path = crate::lib::geometry::line(shooter_pos, target_pos);
+    let Some(cover_tile) = path.iter().rev().nth(1).copied() else {
+        return CoverLevel::None;
+    };
+    if !state.map.can_see_through(cover_tile) {
+        return CoverLevel::Full;
+    }
+    let tile_cover = state
+        .map
+        .tile(cover_tile)
+        .map(|tile| tile.cover())
+        .unwrap_or(CoverLevel::None);
+    //props that give cover are collideable, so whatever's on the tile is in the occupancy grid
+    state
+        .occupancy
+        .at(cover_tile)
+        .and_then(|prop| state.ecs.get::<&Cover>(prop).ok().map(|cover| cover.0))
+        .map_or(tile_cover, |cover| tile_cover.max(cover))
+}
+
+///Pulls together everything that affects an attack from one entity on another out of the game state
+pub fn gather_inputs(
+    state: &GameState,
+    attacker: Entity,
+    target: Entity,
+    kind: AttackKind,
+) -> Option<HitChanceInputs> {
+    let attacker_pos = *state.ecs.get::<&IVec2>(attacker).ok()?;
+    let target_pos = *state.ecs.get::<&IVec2>(target).ok()?;
+    let target_status = match state.ecs.get::<&StatusEffects>(target) {
+        Ok(status) => status.get().clone(),
+        Err(_) => Vec::new(),
+    };
+    let (weapon_accuracy, range_band, fire_mode) = match kind {
+        AttackKind::Ranged => {
+            let weapon = equipped_ranged_weapon(&state.ecs, attacker)?;
+            (
+                weapon.accuracy,
+                RangeBand::from_distance(distance(attacker_pos, target_pos), weapon.range),
+                selected_fire_mode(&state.ecs, attacker),
+            )
+        }
+        AttackKind::Melee => {
+            let weapon = equipped_melee_weapon(&state.ecs, attacker)?;
+            (weapon.accuracy, RangeBand::Melee, FireMode::Single)
+        }
+    };
+    Some(HitChanceInputs {
+        weapon_accuracy,
+        range_band,
+        cover: cover_against(state, attacker_pos, target_pos),
+        target_status,
+        attacker_skill: attack_skill_bonus(&state.ecs, attacker, kind),
+        fire_mode,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///a plain shot at short range with nothing helping or hindering it
+    fn inputs(weapon_accuracy: i32) -> HitChanceInputs {
+        HitChanceInputs {
+            weapon_accuracy,
+            range_band: RangeBand::Short,
+            cover: CoverLevel::None,
+            target_status: Vec::new(),
+            attacker_skill: 0,
+            fire_mode: FireMode::Single,
+        }
+    }
+
+    #[test]
+    fn never_drops_below_the_minimum() {
+        let chance = hit_chance(&HitChanceInputs {
+            cover: CoverLevel::Full,
+            range_band: RangeBand::OutOfRange,
+            ..inputs(20)
+        });
+        assert_eq!(chance.probability, MIN_HIT_CHANCE);
+        //the limit shows up in the breakdown so it still adds up to the final chance
+        let total: i32 = chance
+            .breakdown
+            .iter()
+            .map(|(_reason, amount)| amount)
+            .sum();
+        assert_eq!(total, MIN_HIT_CHANCE);
+    }
+
+    #[test]
+    fn never_goes_above_the_maximum() {
+        let chance = hit_chance(&HitChanceInputs {
+            range_band: RangeBand::PointBlank,
+            attacker_skill: 20,
+            ..inputs(90)
+        });
+        assert_eq!(chance.probability, MAX_HIT_CHANCE);
+        let total: i32 = chance
+            .breakdown
+            .iter()
+            .map(|(_reason, amount)| amount)
+            .sum();
+        assert_eq!(total, MAX_HIT_CHANCE);
+    }
+
+    #[test]
+    fn better_cover_is_harder_to_hit() {
+        let probabilities: Vec<i32> = [
+            CoverLevel::None,
+            CoverLevel::Quarter,
+            CoverLevel::Half,
+            CoverLevel::Full,
+        ]
+        .iter()
+        .map(|cover| {
+            hit_chance(&HitChanceInputs {
+                cover: *cover,
+                ..inputs(70)
+            })
+            .probability
+        })
+        .collect();
+        assert_eq!(probabilities, vec![70, 60, 45, 30]);
+    }
+
+    #[test]
+    fn range_bands_fall_off_with_distance() {
+        let range = 8;
+        let bands: Vec<RangeBand> = [1, 4, 6, 8, 9]
+            .iter()
+            .map(|distance| RangeBand::from_distance(*distance, range))
+            .collect();
+        assert_eq!(
+            bands,
+            vec![
+                RangeBand::PointBlank,
+                RangeBand::Short,
+                RangeBand::Medium,
+                RangeBand::Long,
+                RangeBand::OutOfRange,
+            ]
+        );
+        let probabilities: Vec<i32> = bands
+            .iter()
+            .map(|band| {
+                hit_chance(&HitChanceInputs {
+                    range_band: *band,
+                    ..inputs(70)
+                })
+                .probability
+            })
+            .collect();
+        assert_eq!(probabilities, vec![85, 70, 60, 45, MIN_HIT_CHANCE]);
+    }
+
+    #[test]
+    fn target_status_is_itemised() {
+        let chance = hit_chance(&HitChanceInputs {
+            target_status: vec![StatusEffect::Hunkered],
+            ..inputs(70)
+        });
+        assert_eq!(chance.probability, 50);
+        assert!(chance
+            .breakdown
+            .contains(&(String::from("Target hunkered down"), -20)));
+    }
+}
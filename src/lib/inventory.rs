@@ -0,0 +1,212 @@
+/*
+This module contains the helpers for managing what combatants carry: equipping and unequipping gear, swapping
+weapons and dropping and picking up items on the map. Items are ordinary entities, they're either pointed to by
+someone's equipment or inventory, or they're lying on a tile with an IVec2 of their own
+*/
+use crate::prelude::*;
+use hecs::*;
+use macroquad::prelude::*;
+
+///One line of someone's gear, either something in an equipment slot or something in their inventory
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct GearEntry {
+    ///the slot it's equipped in, None for things that are just being carried
+    pub slot: Option<EquipmentSlot>,
+    pub item: Option<Entity>,
+}
+
+///Lists everything an entity has, each equipment slot first (even if it's empty) and then their inventory
+pub fn gear_list(ecs: &World, entity: Entity) -> Vec<GearEntry> {
+    let mut gear: Vec<GearEntry> = Vec::new();
+    if let Ok(equipment) = ecs.get::<&Equipment>(entity) {
+        for slot in EquipmentSlot::ALL {
+            gear.push(GearEntry {
+                slot: Some(slot),
+                item: equipment.get(slot),
+            });
+        }
+    }
+    if let Ok(inventory) = ecs.get::<&Inventory>(entity) {
+        for item in inventory.items() {
+            gear.push(GearEntry {
+                slot: None,
+                item: Some(*item),
+            });
+        }
+    }
+    gear
+}
+
+///checks if an item is a weapon, which can go in either the primary or sidearm slot
+pub fn is_weapon(ecs: &World, item: Entity) -> bool {
+    ecs.get::<&RangedWeapon>(item).is_ok() || ecs.get::<&MeleeWeapon>(item).is_ok()
+}
+
+///Works out which slot an item would be equipped into. Weapons go into whichever weapon slot is in hand so equipping
+///one means drawing it, armour and throwables have their own slots and anything else can't be equipped at all
+pub fn slot_for(ecs: &World, equipment: &Equipment, item: Entity) -> Option<EquipmentSlot> {
+    if is_weapon(ecs, item) {
+        Some(equipment.ready())
+    } else if ecs.get::<&Armour>(item).is_ok() {
+        Some(EquipmentSlot::Armour)
+    } else if ecs.get::<&Throwable>(item).is_ok() {
+        Some(EquipmentSlot::Utility)
+    } else {
+        None
+    }
+}
+
+///Brings the Equipped* tags on an entity back in line with their equipment. The weapon in hand is the one that's
+///shot with, a melee weapon can be used whether it's in hand or stowed, and whatever's in the utility slot is thrown
+pub fn refresh_equipped(ecs: &mut World, entity: Entity) {
+    let Ok(equipment) = ecs.get::<&Equipment>(entity).map(|equipment| *equipment) else {
+        return;
+    };
+    let in_hand = equipment.get(equipment.ready());
+    let stowed = equipment.get(equipment.stowed());
+    let ranged = in_hand.filter(|item| ecs.get::<&RangedWeapon>(*item).is_ok());
+    let melee = [in_hand, stowed]
+        .into_iter()
+        .flatten()
+        .find(|item| ecs.get::<&MeleeWeapon>(*item).is_ok());
+    let throwable = equipment
+        .get(EquipmentSlot::Utility)
+        .filter(|item| ecs.get::<&Throwable>(*item).is_ok());
+    match ranged {
+        Some(weapon) => {
+            let _ = ecs.insert_one(entity, EquippedRangedWeapon::new(weapon));
+        }
+        None => {
+            let _ = ecs.remove_one::<EquippedRangedWeapon>(entity);
+        }
+    }
+    match melee {
+        Some(weapon) => {
+            let _ = ecs.insert_one(entity, EquippedMeleeWeapon::new(weapon));
+        }
+        None => {
+            let _ = ecs.remove_one::<EquippedMeleeWeapon>(entity);
+        }
+    }
+    match throwable {
+        Some(item) => {
+            let _ = ecs.insert_one(entity, EquippedThrowable::new(item));
+        }
+        None => {
+            let _ = ecs.remove_one::<EquippedThrowable>(entity);
+        }
+    }
+}
+
+///returns the armour an entity is wearing, if they're wearing any
+pub fn equipped_armour(ecs: &World, entity: Entity) -> Option<Armour> {
+    let item = ecs
+        .get::<&Equipment>(entity)
+        .ok()?
+        .get(EquipmentSlot::Armour)?;
+    ecs.get::<&Armour>(item).ok().map(|armour| *armour)
+}
+
+///Equips an item out of an entity's inventory, putting whatever was in the slot before back in the inventory.
+///Returns the slot it went in
+pub fn equip(ecs: &mut World, entity: Entity, item: Entity) -> Result<EquipmentSlot, String> {
+    let mut equipment = *ecs
+        .get::<&Equipment>(entity)
+        .map_err(|_| String::from("they can't equip anything"))?;
+    let slot = slot_for(ecs, &equipment, item).ok_or(String::from("it can't be equipped"))?;
+    {
+        let mut inventory = ecs
+            .get::<&mut Inventory>(entity)
+            .map_err(|_| String::from("they aren't carrying it"))?;
+        if !inventory.remove(item) {
+            return Err(String::from("they aren't carrying it"));
+        }
+        if let Some(replaced) = equipment.set(slot, Some(item)) {
+            inventory.add(replaced);
+        }
+    }
+    let _ = ecs.insert_one(entity, equipment);
+    refresh_equipped(ecs, entity);
+    Ok(slot)
+}
+
+///Takes whatever's in a slot off and puts it in the entity's inventory. Returns the item that was taken off
+pub fn unequip(ecs: &mut World, entity: Entity, slot: EquipmentSlot) -> Result<Entity, String> {
+    let mut equipment = *ecs
+        .get::<&Equipment>(entity)
+        .map_err(|_| String::from("they don't have anything equipped"))?;
+    let item = equipment
+        .set(slot, None)
+        .ok_or(String::from("there's nothing in that slot"))?;
+    ecs.get::<&mut Inventory>(entity)
+        .map_err(|_| String::from("they have nowhere to carry it"))?
+        .add(item);
+    let _ = ecs.insert_one(entity, equipment);
+    refresh_equipped(ecs, entity);
+    Ok(item)
+}
+
+///Switches the entity over to the weapon slot that isn't in hand and returns the weapon they're now holding
+pub fn swap_weapons(ecs: &mut World, entity: Entity) -> Result<Option<Entity>, String> {
+    let mut equipment = *ecs
+        .get::<&Equipment>(entity)
+        .map_err(|_| String::from("they don't have any weapons"))?;
+    if equipment.get(equipment.stowed()).is_none() {
+        return Err(format!(
+            "there's nothing in their {} slot",
+            equipment.stowed().label().to_lowercase()
+        ));
+    }
+    equipment.swap_ready();
+    let _ = ecs.insert_one(entity, equipment);
+    refresh_equipped(ecs, entity);
+    Ok(equipment.get(equipment.ready()))
+}
+
+///Stops an entity having an item at all, whether it was equipped or being carried. Used when something is dropped
+///or used up
+pub fn remove_item(ecs: &mut World, entity: Entity, item: Entity) {
+    if let Ok(inventory) = ecs.query_one_mut::<&mut Inventory>(entity) {
+        inventory.remove(item);
+    }
+    if let Ok(equipment) = ecs.query_one_mut::<&mut Equipment>(entity) {
+        if let Some(slot) = equipment.slot_of(item) {
+            equipment.set(slot, None);
+        }
+    }
+    refresh_equipped(ecs, entity);
+}
+
+///Drops an item the entity has onto a tile
+pub fn drop_item(ecs: &mut World, entity: Entity, item: Entity, tile: IVec2) {
+    remove_item(ecs, entity, item);
+    let _ = ecs.insert_one(item, tile);
+}
+
+///returns every item lying on a tile
+pub fn items_at(ecs: &World, tile: IVec2) -> Vec<Entity> {
+    ecs.query::<(&Item, &IVec2)>()
+        .iter()
+        .filter(|(_id, (_item, pos))| **pos == tile)
+        .map(|(id, _)| id)
+        .collect()
+}
+
+///Picks up everything lying on a tile into the entity's inventory and returns what was picked up. Anything that
+///fits an empty slot is equipped straight away
+pub fn pick_up(ecs: &mut World, entity: Entity, tile: IVec2) -> Vec<Entity> {
+    let items = items_at(ecs, tile);
+    for item in items.iter() {
+        let _ = ecs.remove_one::<IVec2>(*item);
+        if let Ok(inventory) = ecs.query_one_mut::<&mut Inventory>(entity) {
+            inventory.add(*item);
+        }
+        let empty_slot = ecs.get::<&Equipment>(entity).ok().and_then(|equipment| {
+            slot_for(ecs, &equipment, *item).filter(|slot| equipment.get(*slot).is_none())
+        });
+        if empty_slot.is_some() {
+            let _ = equip(ecs, entity, *item);
+        }
+    }
+    items
+}
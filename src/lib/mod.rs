@@ -2,4 +2,14 @@
 This folder contains all of the miscellanious helper functions that are critical to the game
 but wouldn't quite work as methods of components or bespoke game systems
 */
+pub mod abilities;
+pub mod blast;
+pub mod control;
+pub mod format;
+pub mod geometry;
+pub mod hit_chance;
+pub mod inventory;
+pub mod reactions;
+pub mod skills;
 pub mod systems;
+pub mod targeting;
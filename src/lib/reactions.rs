@@ -0,0 +1,84 @@
+/*
+This module works out who gets to act outside of their own turn, like someone on overwatch taking a shot at a hostile
+that walks into the area they're watching
+*/
+use super::geometry::*;
+use super::targeting::*;
+use crate::prelude::*;
+use hecs::*;
+use macroquad::prelude::*;
+
+///checks if two entities are on opposing sides. Anything that isn't a combatant isn't hostile to anyone
+pub fn is_hostile(ecs: &World, a: Entity, b: Entity) -> bool {
+    match (ecs.get::<&ControlType>(a), ecs.get::<&ControlType>(b)) {
+        (Ok(a), Ok(b)) => *a != *b,
+        _ => false,
+    }
+}
+
+///checks if a watcher could take a shot at a tile from where they are, with the weapon they've got equipped
+pub fn can_watch_tile(
+    state: &GameState,
+    watcher: Entity,
+    overwatch: &Overwatch,
+    tile: IVec2,
+) -> bool {
+    let (Ok(pos), Some(weapon)) = (
+        state.ecs.get::<&IVec2>(watcher).map(|pos| *pos),
+        equipped_ranged_weapon(&state.ecs, watcher),
+    ) else {
+        return false;
+    };
+    pos != tile
+        && distance(pos, tile) <= weapon.range
+        && overwatch.watches(pos, tile)
+        && trace_line_of_fire(state, pos, tile).1.is_none()
+}
+
+///Returns everyone on overwatch who gets a reaction shot at the mover now that they've stepped onto a tile,
+///in the order they'll take their shots
+pub fn overwatch_reactors(state: &GameState, mover: Entity, tile: IVec2) -> Vec<Entity> {
+    let watchers: Vec<(Entity, Overwatch)> = state
+        .ecs
+        .query::<&Overwatch>()
+        .iter()
+        .map(|(watcher, overwatch)| (watcher, *overwatch))
+        .collect();
+    watchers
+        .into_iter()
+        .filter(|(watcher, overwatch)| {
+            *watcher != mover
+                && is_hostile(&state.ecs, *watcher, mover)
+                && can_watch_tile(state, *watcher, overwatch, tile)
+        })
+        .map(|(watcher, _overwatch)| watcher)
+        .collect()
+}
+
+///Returns every hostile whose zone of control the mover is leaving by stepping from one tile to another. Anyone with
+///a melee weapon controls the eight tiles around them, diagonals included, and moving out of them provokes a free
+///attack. Stepping to another tile that's still next to them doesn't count as leaving
+pub fn opportunity_attackers(
+    state: &GameState,
+    mover: Entity,
+    from: IVec2,
+    to: IVec2,
+) -> Vec<Entity> {
+    //only the tiles around where the mover started can hold someone close enough to take a swing
+    let around: Vec<IVec2> = (-1..=1)
+        .flat_map(|dy| (-1..=1).map(move |dx| from + IVec2::new(dx, dy)))
+        .filter(|tile| *tile != from)
+        .collect();
+    state
+        .occupancy
+        .occupants(&around)
+        .into_iter()
+        .filter(|(attacker, pos)| {
+            *attacker != mover
+                && !is_adjacent(*pos, to)
+                && state.ecs.get::<&EquippedMeleeWeapon>(*attacker).is_ok()
+                && is_hostile(&state.ecs, *attacker, mover)
+        })
+        .map(|(attacker, _pos)| attacker)
+        .collect()
+}
@@ -0,0 +1,165 @@
+/*
+This module contains the skill checks. A check rolls percentile dice against a target worked out from the character's
+attribute and training, and rather than just passing or failing it says by how much, so a close call and a clean
+success can play out differently
+*/
+use crate::prelude::*;
+use hecs::*;
+
+///how much each point of an attribute above or below average moves a check's target
+pub const ATTRIBUTE_STEP: i32 = 5;
+///how much each rank of training in a skill adds to a check's target
+pub const RANK_STEP: i32 = 10;
+///an untrained character with average attributes has this chance to pass a check
+pub const BASE_SKILL_TARGET: i32 = 40;
+///how many points past the target it takes to get another degree of success or failure
+pub const DEGREE_STEP: i32 = 10;
+///how much each point of an attribute and rank of training adds to the chance of hitting with a weapon
+pub const ATTACK_SKILL_STEP: i32 = 5;
+
+///The outcome of a skill check
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SkillCheck {
+    pub skill: Skill,
+    ///what was rolled on the percentile dice, anything under the target passes
+    pub roll: i32,
+    pub target: i32,
+    ///How well it went. 0 or more is a success, counting up by one for every 10 points under the target it was
+    ///rolled. Below 0 is a failure, with -1 being only just missing
+    pub degrees: i32,
+}
+impl SkillCheck {
+    pub fn succeeded(&self) -> bool {
+        self.degrees >= 0
+    }
+    ///a few words describing how well it went, for the log
+    pub fn describe(&self) -> &'static str {
+        match self.degrees {
+            3.. => "an outstanding success",
+            1..=2 => "a solid success",
+            0 => "a narrow success",
+            -1 => "a narrow failure",
+            -3..=-2 => "a clear failure",
+            _ => "a disastrous failure",
+        }
+    }
+}
+
+///Works out the number a character has to roll under to pass a check, before any situational modifiers
+pub fn skill_target(ecs: &World, entity: Entity, skill: Skill) -> i32 {
+    let attribute = match ecs.get::<&Attributes>(entity) {
+        Ok(attributes) => attributes.modifier(skill.attribute()),
+        Err(_) => 0,
+    };
+    let ranks = match ecs.get::<&Skills>(entity) {
+        Ok(skills) => skills.rank(skill),
+        Err(_) => 0,
+    };
+    BASE_SKILL_TARGET + attribute * ATTRIBUTE_STEP + ranks * RANK_STEP
+}
+
+///Turns a roll against a target into degrees of success, see SkillCheck::degrees
+pub fn degrees_of_success(roll: i32, target: i32) -> i32 {
+    if roll < target {
+        (target - 1 - roll) / DEGREE_STEP
+    } else {
+        -((roll - target) / DEGREE_STEP) - 1
+    }
+}
+
+///Makes a skill check for an entity, with a modifier for whatever's making it easier or harder
+pub fn skill_check(
+    state: &mut GameState,
+    entity: Entity,
+    skill: Skill,
+    modifier: i32,
+) -> SkillCheck {
+    let target = skill_target(&state.ecs, entity, skill) + modifier;
+    let roll = state.rng.percentile(RngStream::Skill);
+    SkillCheck {
+        skill,
+        roll,
+        target,
+        degrees: degrees_of_success(roll, target),
+    }
+}
+
+///The bonus to hit an attacker gets from their attribute and training with the kind of weapon they're using
+pub fn attack_skill_bonus(ecs: &World, entity: Entity, kind: AttackKind) -> i32 {
+    let skill = match kind {
+        AttackKind::Ranged => Skill::Marksmanship,
+        AttackKind::Melee => Skill::Melee,
+    };
+    let attribute = match ecs.get::<&Attributes>(entity) {
+        Ok(attributes) => attributes.modifier(skill.attribute()),
+        Err(_) => 0,
+    };
+    let ranks = match ecs.get::<&Skills>(entity) {
+        Ok(skills) => skills.rank(skill),
+        Err(_) => 0,
+    };
+    (attribute + ranks) * ATTACK_SKILL_STEP
+}
+
+///what a character adds to their initiative roll
+pub fn initiative_bonus(ecs: &World, entity: Entity) -> i32 {
+    match ecs.get::<&Attributes>(entity) {
+        Ok(attributes) => attributes.initiative_bonus(),
+        Err(_) => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolling_just_under_the_target_is_a_narrow_success() {
+        assert_eq!(degrees_of_success(49, 50), 0);
+        assert_eq!(degrees_of_success(40, 50), 0);
+    }
+
+    #[test]
+    fn every_ten_under_the_target_is_another_degree_of_success() {
+        assert_eq!(degrees_of_success(39, 50), 1);
+        assert_eq!(degrees_of_success(30, 50), 1);
+        assert_eq!(degrees_of_success(29, 50), 2);
+        assert_eq!(degrees_of_success(0, 50), 4);
+    }
+
+    #[test]
+    fn rolling_the_target_is_a_narrow_failure() {
+        assert_eq!(degrees_of_success(50, 50), -1);
+        assert_eq!(degrees_of_success(59, 50), -1);
+    }
+
+    #[test]
+    fn every_ten_over_the_target_is_another_degree_of_failure() {
+        assert_eq!(degrees_of_success(60, 50), -2);
+        assert_eq!(degrees_of_success(69, 50), -2);
+        assert_eq!(degrees_of_success(70, 50), -3);
+        assert_eq!(degrees_of_success(99, 50), -5);
+    }
+
+    #[test]
+    fn targets_out_of_the_dice_range_still_work() {
+        assert_eq!(degrees_of_success(0, 0), -1);
+        assert_eq!(degrees_of_success(99, 120), 2);
+    }
+
+    #[test]
+    fn success_matches_the_degrees() {
+        let check = |degrees| SkillCheck {
+            skill: Skill::Resolve,
+            roll: 0,
+            target: 0,
+            degrees,
+        };
+        assert!(check(0).succeeded());
+        assert!(!check(-1).succeeded());
+        assert_eq!(check(0).describe(), "a narrow success");
+        assert_eq!(check(-1).describe(), "a narrow failure");
+        assert_eq!(check(3).describe(), "an outstanding success");
+        assert_eq!(check(-4).describe(), "a disastrous failure");
+    }
+}
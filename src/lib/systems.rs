@@ -3,14 +3,155 @@ This module contains all the helper functions for the game systems
 */
 use crate::prelude::*;
 use hecs::*;
+use macroquad::prelude::*;
 ///Reset the amount of movement points an entity has so they can move more than once ever.
 pub fn refresh_mp(state: &mut GameState, active_entity: Entity) {
     //the way I'm doing it rn feels really hacky and lazy but whatever
-    for mp in state
+    if let Ok(mp) = state
         .ecs
         .query_one_mut::<&mut MovementPoints>(active_entity)
     {
         mp.reset();
-        println!("resetting movement points.");
+        state.ecs.spawn((DebugLogMessage::new(
+            DebugLevel::Debug,
+            Subsystem::Movement,
+            crate::lib::format::format_message(
+                "resetting {actor}'s movement points.",
+                &[(
+                    "actor",
+                    crate::lib::format::entity_name(&state.ecs, active_entity),
+                )],
+            ),
+        ),));
     }
 }
+
+///Refills an entity's action points for a new round, taking off whatever their status effects cost them and
+///giving them their bonus actions back
+pub fn refresh_action_points(state: &mut GameState, entity: Entity) {
+    let modifier = match state.ecs.get::<&StatusEffects>(entity) {
+        Ok(status) => state.actions.ap_modifier(&status),
+        Err(_) => 0,
+    };
+    let bonus_actions = state.actions.bonus_actions();
+    if let Ok(ap) = state.ecs.query_one_mut::<&mut ActionPoints>(entity) {
+        ap.reset(modifier, bonus_actions);
+    }
+    if modifier != 0 {
+        state.ecs.spawn((GameLogMessage::new(
+            LogCategory::Turn,
+            crate::lib::format::format_message(
+                "{actor} starts the round with {modifier} AP from their condition.",
+                &[
+                    ("actor", crate::lib::format::entity_name(&state.ecs, entity)),
+                    ("modifier", format!("{:+}", modifier)),
+                ],
+            ),
+        ),));
+    }
+}
+
+///Takes back the last step the entity took this turn, putting them back where they were with the movement points
+///they had. Nothing happens if there's nothing to undo or if someone else is now standing on the tile they came from
+pub fn undo_move(state: &mut GameState, entity: Entity) -> Option<MoveRecord> {
+    let from = state.ecs.get::<&MoveHistory>(entity).ok()?.last()?.from;
+    if state.occupancy.is_blocked_for(entity, from) {
+        return None;
+    }
+    let record = state.ecs.get::<&mut MoveHistory>(entity).ok()?.pop()?;
+    if let Ok((pos, movement_points)) = state
+        .ecs
+        .query_one_mut::<(&mut IVec2, &mut MovementPoints)>(entity)
+    {
+        *pos = record.from;
+        *movement_points = record.movement_points;
+    }
+    state.occupancy.move_entity(entity, record.to, record.from);
+    if let Ok(visual_pos) = state.ecs.query_one_mut::<&mut VisualPosition>(entity) {
+        visual_pos.move_to(record.from, state.config.move_duration);
+    }
+    if let Ok(facing) = state.ecs.query_one_mut::<&mut Facing>(entity) {
+        *facing = Facing::from_delta(record.from - record.to, *facing);
+    }
+    Some(record)
+}
+
+///Forgets all the steps the entity has taken so far, used once they've done something that can't be taken back
+pub fn clear_move_history(state: &mut GameState, entity: Entity) {
+    if let Ok(history) = state.ecs.query_one_mut::<&mut MoveHistory>(entity) {
+        history.clear();
+    }
+}
+
+///Spawns an entity mid-game, putting it in the occupancy grid if it's collideable so nothing can walk through it.
+///The scenario's own entities are picked up when the grid is built instead
+pub fn spawn_entity(state: &mut GameState, components: impl DynamicBundle) -> Entity {
+    let entity = state.ecs.spawn(components);
+    let blocking = state.ecs.get::<&Collideable>(entity).is_ok();
+    if let (true, Ok(pos)) = (blocking, state.ecs.get::<&IVec2>(entity).map(|pos| *pos)) {
+        state.occupancy.place(entity, pos);
+    }
+    entity
+}
+
+///Despawns an entity, taking it out of the occupancy grid first so no tile is left pointing at something that's gone
+pub fn despawn_entity(state: &mut GameState, entity: Entity) {
+    stop_blocking(state, entity);
+    let _ = state.ecs.despawn(entity);
+}
+
+///Makes an entity stop getting in the way, like a combatant that's just gone down. They stay where they are but are
+///taken out of the occupancy grid
+pub fn stop_blocking(state: &mut GameState, entity: Entity) {
+    let _ = state.ecs.remove_one::<Collideable>(entity);
+    if let Ok(pos) = state.ecs.get::<&IVec2>(entity).map(|pos| *pos) {
+        state.occupancy.remove(entity, pos);
+    }
+}
+
+///the dice everyone rolls for their place in the initiative order
+pub const INITIATIVE_DICE: Dice = Dice {
+    count: 1,
+    sides: 20,
+    modifier: 0,
+};
+
+///Rolls initiative for everyone in the combat encounter, adding their bonus from attributes, and replaces it with one
+///in the order they rolled, highest first. Ties keep whoever was listed first ahead
+pub fn roll_initiative(state: &mut GameState) {
+    let mut encounter_id: Option<Entity> = None;
+    let mut combatants: Vec<Entity> = Vec::new();
+    for (id, combat_encounter) in state.ecs.query_mut::<&CombatEncounter>() {
+        encounter_id = Some(id);
+        combatants = combat_encounter.get_all_entities();
+    }
+    let Some(encounter_id) = encounter_id else {
+        return;
+    };
+    let mut rolls: Vec<(Entity, i32)> = Vec::new();
+    for entity in combatants.iter() {
+        //quicker characters get a head start on the roll
+        let bonus = crate::lib::skills::initiative_bonus(&state.ecs, *entity);
+        let roll = state.rng.roll(RngStream::Initiative, &INITIATIVE_DICE) + bonus;
+        rolls.push((*entity, roll));
+        state.ecs.spawn((GameLogMessage::new(
+            LogCategory::Turn,
+            crate::lib::format::format_message(
+                "{actor} rolls {roll} for initiative ({bonus} from attributes).",
+                &[
+                    (
+                        "actor",
+                        crate::lib::format::entity_name(&state.ecs, *entity),
+                    ),
+                    ("roll", roll.to_string()),
+                    ("bonus", format!("{:+}", bonus)),
+                ],
+            ),
+        ),));
+    }
+    rolls.sort_by_key(|(_entity, roll)| std::cmp::Reverse(*roll));
+    let order: Vec<Entity> = rolls.iter().map(|(entity, _roll)| *entity).collect();
+    let _ = state
+        .ecs
+        .insert_one(encounter_id, CombatEncounter::new(order));
+}
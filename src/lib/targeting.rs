@@ -0,0 +1,159 @@
+/*
+This module contains the helpers for aiming at things: finding the reticule, working out what's under it and
+whether there's a clear line of fire to it
+*/
+use super::geometry::*;
+use crate::prelude::*;
+use hecs::*;
+use macroquad::prelude::*;
+
+///Everything the player needs to know about where they're aiming
+#[derive(Clone, Debug)]
+pub struct TargetingInfo {
+    pub distance: i32,
+    pub range: i32,
+    ///the tiles a shot would travel through on its way to the reticule
+    pub path: Vec<IVec2>,
+    ///the first tile along the path that stops the shot, if anything does
+    pub blocked_at: Option<IVec2>,
+    ///the combatant standing under the reticule, if there is one
+    pub target: Option<Entity>,
+}
+impl TargetingInfo {
+    pub fn in_range(&self) -> bool {
+        self.distance <= self.range
+    }
+    pub fn has_line_of_fire(&self) -> bool {
+        self.blocked_at.is_none()
+    }
+    ///checks if a shot could actually be taken at whatever's under the reticule
+    pub fn is_valid(&self) -> bool {
+        self.target.is_some() && self.in_range() && self.has_line_of_fire()
+    }
+}
+
+///returns the targeting reticule and where it is, if one has been spawned
+pub fn find_reticule(ecs: &World) -> Option<(Entity, IVec2)> {
+    ecs.query::<(&Reticule, &IVec2)>()
+        .iter()
+        .next()
+        .map(|(id, (_reticule, pos))| (id, *pos))
+}
+
+///returns the ranged weapon an entity has equipped, if they have one
+pub fn equipped_ranged_weapon(ecs: &World, entity: Entity) -> Option<RangedWeapon> {
+    let weapon = ecs.get::<&EquippedRangedWeapon>(entity).ok()?.get();
+    ecs.get::<&RangedWeapon>(weapon).ok().map(|weapon| *weapon)
+}
+
+///returns the weapon entity an entity has equipped as their ranged weapon, if they have one
+pub fn equipped_ranged_weapon_entity(ecs: &World, entity: Entity) -> Option<Entity> {
+    ecs.get::<&EquippedRangedWeapon>(entity)
+        .ok()
+        .map(|equipped| equipped.get())
+}
+
+///returns the fire mode an entity's ranged weapon is set to, single shots if it hasn't been set
+pub fn selected_fire_mode(ecs: &World, entity: Entity) -> FireMode {
+    equipped_ranged_weapon_entity(ecs, entity)
+        .and_then(|weapon| ecs.get::<&SelectedFireMode>(weapon).ok().map(|mode| mode.0))
+        .unwrap_or(FireMode::Single)
+}
+
+///returns the magazine of an entity's ranged weapon, if it has one
+pub fn equipped_magazine(ecs: &World, entity: Entity) -> Option<Magazine> {
+    let weapon = equipped_ranged_weapon_entity(ecs, entity)?;
+    ecs.get::<&Magazine>(weapon).ok().map(|magazine| *magazine)
+}
+
+///Explains why an entity's ranged weapon can't be fired in the mode it's set to, if it can't. Weapons that don't
+///need loading can always be fired
+pub fn ammo_problem(ecs: &World, entity: Entity) -> Option<String> {
+    let magazine = equipped_magazine(ecs, entity)?;
+    let mode = selected_fire_mode(ecs, entity);
+    if magazine.is_empty() {
+        Some(String::from("their weapon is empty and needs reloading"))
+    } else if magazine.loaded() < mode.rounds() {
+        Some(format!(
+            "{} needs {} rounds and there are only {} loaded",
+            mode.label().to_lowercase(),
+            mode.rounds(),
+            magazine.loaded()
+        ))
+    } else {
+        None
+    }
+}
+
+///returns the melee weapon an entity has equipped, if they have one
+pub fn equipped_melee_weapon(ecs: &World, entity: Entity) -> Option<MeleeWeapon> {
+    let weapon = ecs.get::<&EquippedMeleeWeapon>(entity).ok()?.get();
+    ecs.get::<&MeleeWeapon>(weapon).ok().map(|weapon| *weapon)
+}
+
+///returns the accuracy and damage of whichever weapon an entity would make an attack with
+pub fn attack_weapon(ecs: &World, entity: Entity, kind: AttackKind) -> Option<(i32, Dice)> {
+    match kind {
+        AttackKind::Ranged => {
+            equipped_ranged_weapon(ecs, entity).map(|weapon| (weapon.accuracy, weapon.damage))
+        }
+        AttackKind::Melee => {
+            equipped_melee_weapon(ecs, entity).map(|weapon| (weapon.accuracy, weapon.damage))
+        }
+    }
+}
+
+///returns the combatant standing on a tile, if there is one
+pub fn combatant_at(state: &GameState, tile: IVec2) -> Option<Entity> {
+    state
+        .occupancy
+        .at(tile)
+        .filter(|occupant| state.ecs.get::<&ControlType>(*occupant).is_ok())
+}
+
+///Returns every combatant standing on any of the tiles along with where they are, for anything that hits an area
+pub fn combatants_in(state: &GameState, tiles: &[IVec2]) -> Vec<(Entity, IVec2)> {
+    state
+        .occupancy
+        .occupants(tiles)
+        .into_iter()
+        .filter(|(occupant, _tile)| {
+            state.ecs.get::<&ControlType>(*occupant).is_ok()
+                && state.ecs.get::<&Health>(*occupant).is_ok()
+        })
+        .collect()
+}
+
+///Traces a line of fire between two tiles. Walls stop it, as do any other combatants standing in the way.
+///The tile being shot at doesn't count as being in the way of itself
+pub fn trace_line_of_fire(
+    state: &GameState,
+    from: IVec2,
+    to: IVec2,
+) -> (Vec<IVec2>, Option<IVec2>) {
+    let path = line(from, to);
+    let blocked_at = path.iter().copied().find(|tile| {
+        !state.map.can_see_through(*tile) || (*tile != to && combatant_at(state, *tile).is_some())
+    });
+    (path, blocked_at)
+}
+
+///Works out what the shooter would be aiming at with the reticule where it is. Returns None if the shooter isn't
+///on the map or doesn't have a ranged weapon to aim
+pub fn targeting_info(
+    state: &GameState,
+    shooter: Entity,
+    reticule_pos: IVec2,
+) -> Option<TargetingInfo> {
+    let shooter_pos = *state.ecs.get::<&IVec2>(shooter).ok()?;
+    let weapon = equipped_ranged_weapon(&state.ecs, shooter)?;
+    let (path, blocked_at) = trace_line_of_fire(state, shooter_pos, reticule_pos);
+    let target = combatant_at(state, reticule_pos).filter(|target| *target != shooter);
+    Some(TargetingInfo {
+        distance: distance(shooter_pos, reticule_pos),
+        range: weapon.range,
+        path,
+        blocked_at,
+        target,
+    })
+}
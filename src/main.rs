@@ -1,3 +1,5 @@
+//the helper folder is called lib for historical reasons, it isn't a library target
+#![allow(special_module_name)]
 mod prelude {
     pub const TILE_WIDTH: i32 = 32;
     pub const TILE_HEIGHT: i32 = 32;
@@ -5,18 +7,32 @@ mod prelude {
     //max screen size of 60/33 tiles with 12 pixels at the top and bottom as a buffer (or 24 at the top/bottom of the screen for text)
     pub const MAP_WIDTH: i32 = 45;
     pub const MAP_HEIGHT: i32 = 32;
-    pub use std::collections::HashMap;
     pub const NUM_TILES: usize = (MAP_WIDTH * MAP_HEIGHT) as usize;
+    pub use crate::audio::SoundCategory;
     pub use crate::components::*;
+    pub use crate::debug_log::{DebugLevel, Subsystem};
+    pub use crate::dice::{Dice, RngStream};
+    pub use crate::game_log::*;
     pub use crate::gamestate::*;
 }
+mod ability_registry;
+mod action_economy;
+mod assets;
+mod audio;
 mod combat_action_type;
 mod combat_systems;
 mod components;
+mod config;
+mod debug_log;
+mod dice;
+mod game_log;
 mod gamestate;
+mod hot_reload;
 mod init_ecs;
 mod lib;
 mod map;
+mod occupancy;
+mod replay;
 mod sound_atlas;
 mod texture_atlas;
 
@@ -48,6 +64,8 @@ async fn main() {
 
     //this is the actual gameloop for right now
     loop {
+        //pick up any changes to the assets or map made while the game is running
+        hot_reload::poll(&mut gamestate).await;
         //run all the game systems
         combat_systems::run(&mut gamestate);
         //quit if the player wants to quit
@@ -1,10 +1,35 @@
 use crate::prelude::*;
 use macroquad::prelude::*;
 
+///Where the sandbox map is loaded from, relative to the resources folder. It's the open floor the sandbox has always
+///been fought on, maps/walled.map has some walls to try things against with `--map=maps/walled.map`
+pub const DEFAULT_MAP_PATH: &str = "maps/sandbox.map";
+
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum TileType {
     Wall,
     Floor,
+    ///waist high wall that can be shot over and hidden behind but not walked through. explosives can knock it down
+    LowWall,
+}
+impl TileType {
+    ///the character used for the tile in map files
+    pub fn from_char(glyph: char) -> Option<Self> {
+        match glyph {
+            '#' => Some(TileType::Wall),
+            '.' => Some(TileType::Floor),
+            '=' => Some(TileType::LowWall),
+            _ => None,
+        }
+    }
+    ///how much cover the tile gives to someone hiding right behind it
+    pub fn cover(&self) -> CoverLevel {
+        match self {
+            TileType::Wall => CoverLevel::Full,
+            TileType::Floor => CoverLevel::None,
+            TileType::LowWall => CoverLevel::Half,
+        }
+    }
 }
 pub fn map_idx(x: i32, y: i32) -> usize {
     ((y * MAP_WIDTH) + x) as usize
@@ -21,11 +46,79 @@ impl Map {
         }
     }
 
+    ///Reads a map out of a map file, one line per row with `#` for walls, `=` for low walls and `.` for floor.
+    ///The file has to be exactly MAP_WIDTH by MAP_HEIGHT tiles
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let rows: Vec<&str> = text
+            .lines()
+            .map(|line| line.trim_end())
+            .filter(|line| !line.is_empty())
+            .collect();
+        if rows.len() != MAP_HEIGHT as usize {
+            return Err(format!(
+                "map has {} rows but should have {}",
+                rows.len(),
+                MAP_HEIGHT
+            ));
+        }
+        let mut tiles: Vec<TileType> = Vec::with_capacity(NUM_TILES);
+        for (y, row) in rows.iter().enumerate() {
+            if row.chars().count() != MAP_WIDTH as usize {
+                return Err(format!(
+                    "map row {} is {} tiles wide but should be {}",
+                    y + 1,
+                    row.chars().count(),
+                    MAP_WIDTH
+                ));
+            }
+            for (x, glyph) in row.chars().enumerate() {
+                match TileType::from_char(glyph) {
+                    Some(tile) => tiles.push(tile),
+                    None => return Err(format!("unknown tile '{}' at ({}, {})", glyph, x, y)),
+                }
+            }
+        }
+        Ok(Self { tiles })
+    }
+
+    ///Loads a map file out of the resources folder
+    pub async fn load(path: &str) -> Result<Self, String> {
+        set_pc_assets_folder("resources");
+        let text = load_string(path)
+            .await
+            .map_err(|error| format!("couldn't load map {}: {}", path, error))?;
+        Map::parse(&text).map_err(|error| format!("{}: {}", path, error))
+    }
+
     pub fn in_bounds(&self, point: IVec2) -> bool {
         point.x >= 0 && point.x < MAP_WIDTH && point.y >= 0 && point.y < MAP_HEIGHT
     }
 
+    ///checks if shots and sight can pass through a tile, anything off the map blocks them
+    pub fn can_see_through(&self, point: IVec2) -> bool {
+        self.in_bounds(point) && (self.tiles[map_idx(point.x, point.y)] != TileType::Wall)
+    }
+
     pub fn can_enter_tile(&self, point: IVec2) -> bool {
         self.in_bounds(point) && (self.tiles[map_idx(point.x, point.y)] == TileType::Floor)
     }
+
+    ///returns the tile at a point, if it's on the map
+    pub fn tile(&self, point: IVec2) -> Option<TileType> {
+        if self.in_bounds(point) {
+            Some(self.tiles[map_idx(point.x, point.y)])
+        } else {
+            None
+        }
+    }
+
+    ///Knocks down a low wall, leaving floor behind. Returns true if there was one to knock down, anything sturdier
+    ///is left standing
+    pub fn destroy_cover(&mut self, point: IVec2) -> bool {
+        if self.tile(point) == Some(TileType::LowWall) {
+            self.tiles[map_idx(point.x, point.y)] = TileType::Floor;
+            return true;
+        }
+        false
+    }
 }
@@ -0,0 +1,145 @@
+/*
+This module keeps track of which collideable entity is standing on which tile. Anything that moves, spawns, despawns or
+stops blocking the way has to tell the grid, see the spawn_entity, despawn_entity and stop_blocking helpers in systems
+*/
+use crate::map::map_idx;
+use crate::prelude::*;
+use hecs::*;
+use macroquad::prelude::*;
+
+///A per tile record of which collideable entity is standing where, kept next to the map so collision, targeting and
+///blast checks can ask who's on a tile without going through every entity in the world
+#[derive(Clone)]
+pub struct OccupancyGrid {
+    tiles: Vec<Option<Entity>>,
+}
+impl Default for OccupancyGrid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl OccupancyGrid {
+    pub fn new() -> Self {
+        Self {
+            tiles: vec![None; NUM_TILES],
+        }
+    }
+
+    ///Fills in the grid from everything collideable in the world, for when a scenario has just been set up
+    pub fn build(ecs: &World) -> Self {
+        let mut grid = Self::new();
+        for (id, (pos, _collideable)) in ecs.query::<(&IVec2, &Collideable)>().iter() {
+            grid.place(id, *pos);
+        }
+        grid
+    }
+
+    fn index(tile: IVec2) -> Option<usize> {
+        if tile.x >= 0 && tile.x < MAP_WIDTH && tile.y >= 0 && tile.y < MAP_HEIGHT {
+            Some(map_idx(tile.x, tile.y))
+        } else {
+            None
+        }
+    }
+
+    ///returns whoever is standing on a tile, if anyone is
+    pub fn at(&self, tile: IVec2) -> Option<Entity> {
+        Self::index(tile).and_then(|idx| self.tiles[idx])
+    }
+
+    pub fn is_occupied(&self, tile: IVec2) -> bool {
+        self.at(tile).is_some()
+    }
+
+    ///checks if something other than the entity asking is standing on a tile
+    pub fn is_blocked_for(&self, entity: Entity, tile: IVec2) -> bool {
+        self.at(tile).is_some_and(|occupant| occupant != entity)
+    }
+
+    ///returns everyone standing on any of the tiles along with where they are, once each even if a tile is listed
+    ///more than once
+    pub fn occupants(&self, tiles: &[IVec2]) -> Vec<(Entity, IVec2)> {
+        let mut occupants: Vec<(Entity, IVec2)> = Vec::new();
+        for tile in tiles.iter() {
+            if let Some(occupant) = self.at(*tile) {
+                if !occupants.iter().any(|(other, _tile)| *other == occupant) {
+                    occupants.push((occupant, *tile));
+                }
+            }
+        }
+        occupants
+    }
+
+    ///puts an entity on a tile, anything off the map is ignored
+    pub fn place(&mut self, entity: Entity, tile: IVec2) {
+        if let Some(idx) = Self::index(tile) {
+            self.tiles[idx] = Some(entity);
+        }
+    }
+
+    ///takes an entity off a tile, leaving the tile alone if someone else is on it
+    pub fn remove(&mut self, entity: Entity, tile: IVec2) {
+        if let Some(idx) = Self::index(tile) {
+            if self.tiles[idx] == Some(entity) {
+                self.tiles[idx] = None;
+            }
+        }
+    }
+
+    ///Moves an entity from one tile to another. Only things already in the grid are moved, so it's safe to call for
+    ///anything that changes tiles, reticules and all
+    pub fn move_entity(&mut self, entity: Entity, from: IVec2, to: IVec2) {
+        if self.at(from) == Some(entity) {
+            self.remove(entity, from);
+            self.place(entity, to);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn places_and_removes_entities() {
+        let mut world = World::new();
+        let soldier = world.spawn(());
+        let crate_ = world.spawn(());
+        let mut grid = OccupancyGrid::new();
+        grid.place(soldier, ivec2(2, 3));
+        grid.place(crate_, ivec2(-1, 0));
+        assert_eq!(grid.at(ivec2(2, 3)), Some(soldier));
+        assert!(!grid.is_occupied(ivec2(-1, 0)));
+        //removing someone who isn't on the tile leaves it alone
+        grid.remove(crate_, ivec2(2, 3));
+        assert_eq!(grid.at(ivec2(2, 3)), Some(soldier));
+        grid.remove(soldier, ivec2(2, 3));
+        assert!(!grid.is_occupied(ivec2(2, 3)));
+    }
+
+    #[test]
+    fn only_moves_entities_already_in_the_grid() {
+        let mut world = World::new();
+        let soldier = world.spawn(());
+        let reticule = world.spawn(());
+        let mut grid = OccupancyGrid::new();
+        grid.place(soldier, ivec2(1, 1));
+        grid.move_entity(soldier, ivec2(1, 1), ivec2(2, 1));
+        assert!(!grid.is_occupied(ivec2(1, 1)));
+        assert_eq!(grid.at(ivec2(2, 1)), Some(soldier));
+        grid.move_entity(reticule, ivec2(4, 4), ivec2(5, 5));
+        assert!(!grid.is_occupied(ivec2(5, 5)));
+    }
+
+    #[test]
+    fn nobody_blocks_themselves() {
+        let mut world = World::new();
+        let soldier = world.spawn(());
+        let other = world.spawn(());
+        let mut grid = OccupancyGrid::new();
+        grid.place(soldier, ivec2(3, 3));
+        assert!(!grid.is_blocked_for(soldier, ivec2(3, 3)));
+        assert!(grid.is_blocked_for(other, ivec2(3, 3)));
+        assert!(!grid.is_blocked_for(other, ivec2(4, 3)));
+    }
+}
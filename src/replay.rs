@@ -0,0 +1,375 @@
+/*
+This module records everything needed to play an encounter out again exactly: the seed, the scenario and map it was
+fought on, and every command the player gave. Since all of the game's randomness comes from the seed, feeding the
+same commands back in gives the same result, which turns "it walked through a wall somehow" into something that can
+be watched happening
+*/
+use macroquad::prelude::*;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::Write;
+
+///One thing the player told the game to do. Everything that changes the game state goes through these so it can be
+///recorded, anything that only changes what's on screen (scrolling the log, muting) doesn't
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Command {
+    StartMoving,
+    Step(IVec2),
+    StopMoving,
+    UndoMove,
+    StartTargeting,
+    Aim(IVec2),
+    ConfirmTarget,
+    CancelTargeting,
+    Overwatch,
+    Reload,
+    CycleFireMode,
+    StartThrow,
+    ConfirmThrow,
+    StartMelee,
+    Strike(IVec2),
+    CancelMelee,
+    SwapWeapon,
+    PickUp,
+    OpenGear,
+    ///equips or unequips the entry at that position in the gear list
+    ToggleEquip(usize),
+    ///drops the entry at that position in the gear list
+    DropItem(usize),
+    CloseGear,
+    ///uses the ability in that slot of the action bar, or starts aiming it if it needs a target
+    UseAbility(usize),
+    ConfirmAbility,
+    RequestEndTurn,
+    ConfirmEndTurn,
+    CancelEndTurn,
+}
+impl Command {
+    ///writes the command out the way it's stored in a replay file
+    pub fn to_line(self) -> String {
+        match self {
+            Command::StartMoving => String::from("start_moving"),
+            Command::Step(delta) => format!("step {} {}", delta.x, delta.y),
+            Command::StopMoving => String::from("stop_moving"),
+            Command::UndoMove => String::from("undo_move"),
+            Command::StartTargeting => String::from("start_targeting"),
+            Command::Aim(delta) => format!("aim {} {}", delta.x, delta.y),
+            Command::ConfirmTarget => String::from("confirm_target"),
+            Command::CancelTargeting => String::from("cancel_targeting"),
+            Command::Overwatch => String::from("overwatch"),
+            Command::Reload => String::from("reload"),
+            Command::CycleFireMode => String::from("cycle_fire_mode"),
+            Command::StartThrow => String::from("start_throw"),
+            Command::ConfirmThrow => String::from("confirm_throw"),
+            Command::StartMelee => String::from("start_melee"),
+            Command::Strike(delta) => format!("strike {} {}", delta.x, delta.y),
+            Command::CancelMelee => String::from("cancel_melee"),
+            Command::SwapWeapon => String::from("swap_weapon"),
+            Command::PickUp => String::from("pick_up"),
+            Command::OpenGear => String::from("open_gear"),
+            Command::ToggleEquip(index) => format!("toggle_equip {}", index),
+            Command::DropItem(index) => format!("drop_item {}", index),
+            Command::CloseGear => String::from("close_gear"),
+            Command::UseAbility(slot) => format!("use_ability {}", slot),
+            Command::ConfirmAbility => String::from("confirm_ability"),
+            Command::RequestEndTurn => String::from("request_end_turn"),
+            Command::ConfirmEndTurn => String::from("confirm_end_turn"),
+            Command::CancelEndTurn => String::from("cancel_end_turn"),
+        }
+    }
+    ///reads a command back out of a replay file
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let delta = |words: &[&str]| -> Result<IVec2, String> {
+            match words {
+                [x, y] => match (x.parse::<i32>(), y.parse::<i32>()) {
+                    (Ok(x), Ok(y)) => Ok(IVec2::new(x, y)),
+                    _ => Err(format!("invalid direction in \"{}\"", text)),
+                },
+                _ => Err(format!("expected a direction in \"{}\"", text)),
+            }
+        };
+        let index = |words: &[&str]| -> Result<usize, String> {
+            match words {
+                [index] => index
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid index in \"{}\"", text)),
+                _ => Err(format!("expected an index in \"{}\"", text)),
+            }
+        };
+        match words.first() {
+            Some(&"start_moving") => Ok(Command::StartMoving),
+            Some(&"step") => Ok(Command::Step(delta(&words[1..])?)),
+            Some(&"stop_moving") => Ok(Command::StopMoving),
+            Some(&"undo_move") => Ok(Command::UndoMove),
+            Some(&"start_targeting") => Ok(Command::StartTargeting),
+            Some(&"aim") => Ok(Command::Aim(delta(&words[1..])?)),
+            Some(&"confirm_target") => Ok(Command::ConfirmTarget),
+            Some(&"cancel_targeting") => Ok(Command::CancelTargeting),
+            Some(&"overwatch") => Ok(Command::Overwatch),
+            Some(&"reload") => Ok(Command::Reload),
+            Some(&"cycle_fire_mode") => Ok(Command::CycleFireMode),
+            Some(&"start_throw") => Ok(Command::StartThrow),
+            Some(&"confirm_throw") => Ok(Command::ConfirmThrow),
+            Some(&"start_melee") => Ok(Command::StartMelee),
+            Some(&"strike") => Ok(Command::Strike(delta(&words[1..])?)),
+            Some(&"cancel_melee") => Ok(Command::CancelMelee),
+            Some(&"request_end_turn") => Ok(Command::RequestEndTurn),
+            Some(&"confirm_end_turn") => Ok(Command::ConfirmEndTurn),
+            Some(&"cancel_end_turn") => Ok(Command::CancelEndTurn),
+            Some(&"swap_weapon") => Ok(Command::SwapWeapon),
+            Some(&"pick_up") => Ok(Command::PickUp),
+            Some(&"open_gear") => Ok(Command::OpenGear),
+            Some(&"toggle_equip") => Ok(Command::ToggleEquip(index(&words[1..])?)),
+            Some(&"drop_item") => Ok(Command::DropItem(index(&words[1..])?)),
+            Some(&"close_gear") => Ok(Command::CloseGear),
+            Some(&"use_ability") => Ok(Command::UseAbility(index(&words[1..])?)),
+            Some(&"confirm_ability") => Ok(Command::ConfirmAbility),
+            _ => Err(format!("unknown command \"{}\"", text)),
+        }
+    }
+}
+
+///A command along with the round and turn it was given on
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ReplayEntry {
+    pub round: i32,
+    pub turn: i32,
+    pub command: Command,
+}
+
+///Everything in a replay file
+#[derive(Clone, Debug, Default)]
+pub struct Replay {
+    pub seed: u64,
+    pub scenario: String,
+    pub map_path: String,
+    pub entries: Vec<ReplayEntry>,
+}
+impl Replay {
+    pub fn new(seed: u64, scenario: &str, map_path: &str) -> Self {
+        Self {
+            seed,
+            scenario: scenario.to_string(),
+            map_path: map_path.to_string(),
+            entries: Vec::new(),
+        }
+    }
+    pub fn record(&mut self, round: i32, turn: i32, command: Command) {
+        self.entries.push(ReplayEntry {
+            round,
+            turn,
+            command,
+        });
+    }
+    ///Writes the replay out as text. The header lines hold the seed, scenario and map and each line after that is
+    ///"<round> <turn> <command>"
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        let mut file = File::create(path)?;
+        writeln!(file, "seed {}", self.seed)?;
+        writeln!(file, "scenario {}", self.scenario)?;
+        writeln!(file, "map {}", self.map_path)?;
+        for entry in self.entries.iter() {
+            writeln!(
+                file,
+                "{} {} {}",
+                entry.round,
+                entry.turn,
+                entry.command.to_line()
+            )?;
+        }
+        Ok(())
+    }
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut replay = Replay::default();
+        let mut has_seed = false;
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |error: String| format!("replay line {}: {}", line_number + 1, error);
+            let (first, rest) = line.split_once(' ').unwrap_or((line, ""));
+            match first {
+                "seed" => {
+                    replay.seed = rest
+                        .trim()
+                        .parse::<u64>()
+                        .map_err(|_| error(format!("invalid seed \"{}\"", rest)))?;
+                    has_seed = true;
+                }
+                "scenario" => replay.scenario = rest.trim().to_string(),
+                "map" => replay.map_path = rest.trim().to_string(),
+                _ => {
+                    let mut words = line.splitn(3, ' ');
+                    let round = words.next().and_then(|word| word.parse::<i32>().ok());
+                    let turn = words.next().and_then(|word| word.parse::<i32>().ok());
+                    let (Some(round), Some(turn), Some(command)) = (round, turn, words.next())
+                    else {
+                        return Err(error(format!("couldn't read \"{}\"", line)));
+                    };
+                    replay.record(round, turn, Command::parse(command).map_err(error)?);
+                }
+            }
+        }
+        if !has_seed {
+            return Err(String::from("replay doesn't have a seed"));
+        }
+        Ok(replay)
+    }
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|error| format!("couldn't read replay {}: {}", path, error))?;
+        Replay::parse(&text)
+    }
+}
+
+///how many seconds to wait between commands when a replay is playing at normal speed
+const PLAYBACK_DELAY: f64 = 0.3;
+
+///Feeds a recorded replay's commands back into the game, with controls for pausing, stepping one command at a time
+///and fast forwarding
+pub struct ReplayPlayer {
+    entries: VecDeque<ReplayEntry>,
+    total: usize,
+    paused: bool,
+    fast_forward: bool,
+    step_requested: bool,
+    last_command_time: f64,
+    reported_finished: bool,
+}
+impl ReplayPlayer {
+    pub fn new(replay: &Replay) -> Self {
+        Self {
+            entries: replay.entries.iter().copied().collect(),
+            total: replay.entries.len(),
+            paused: false,
+            fast_forward: false,
+            step_requested: false,
+            last_command_time: 0.0,
+            reported_finished: false,
+        }
+    }
+    ///Hands over the next command if it's time for one. At normal speed that's every so often once nothing is
+    ///animating, fast forward doesn't wait at all, and while paused it's only when a step has been asked for
+    pub fn next(&mut self, now: f64, animating: bool) -> Option<ReplayEntry> {
+        let ready = if self.paused {
+            self.step_requested
+        } else if self.fast_forward {
+            true
+        } else {
+            !animating && now - self.last_command_time >= PLAYBACK_DELAY
+        };
+        if !ready {
+            return None;
+        }
+        self.step_requested = false;
+        self.last_command_time = now;
+        self.entries.pop_front()
+    }
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+    ///plays one more command while paused
+    pub fn step(&mut self) {
+        self.step_requested = true;
+    }
+    pub fn toggle_fast_forward(&mut self) {
+        self.fast_forward = !self.fast_forward;
+    }
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+    pub fn is_fast_forward(&self) -> bool {
+        self.fast_forward
+    }
+    pub fn is_finished(&self) -> bool {
+        self.entries.is_empty()
+    }
+    ///true the first time it's asked after the last command has been played, so the end only gets announced once
+    pub fn just_finished(&mut self) -> bool {
+        if self.is_finished() && !self.reported_finished {
+            self.reported_finished = true;
+            return true;
+        }
+        false
+    }
+    ///how many commands have been played out of how many there are
+    pub fn progress(&self) -> (usize, usize) {
+        (self.total - self.entries.len(), self.total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_read_back_the_way_they_were_written() {
+        let commands = [
+            Command::StartMoving,
+            Command::Step(IVec2::new(-1, 1)),
+            Command::StopMoving,
+            Command::UndoMove,
+            Command::StartTargeting,
+            Command::Overwatch,
+            Command::Reload,
+            Command::CycleFireMode,
+            Command::SwapWeapon,
+            Command::PickUp,
+            Command::OpenGear,
+            Command::ToggleEquip(2),
+            Command::DropItem(0),
+            Command::CloseGear,
+            Command::UseAbility(1),
+            Command::ConfirmAbility,
+            Command::StartMelee,
+            Command::Strike(IVec2::new(1, 1)),
+            Command::CancelMelee,
+            Command::StartThrow,
+            Command::ConfirmThrow,
+            Command::Aim(IVec2::new(0, -1)),
+            Command::ConfirmTarget,
+            Command::CancelTargeting,
+            Command::RequestEndTurn,
+            Command::ConfirmEndTurn,
+            Command::CancelEndTurn,
+        ];
+        for command in commands {
+            assert_eq!(Command::parse(&command.to_line()), Ok(command));
+        }
+    }
+
+    #[test]
+    fn reads_a_replay_file() {
+        let replay = Replay::parse(
+            "seed 1234\nscenario sandbox\nmap maps/sandbox.map\n# a comment\n1 1 start_moving\n1 1 step 1 0\n",
+        )
+        .unwrap();
+        assert_eq!(replay.seed, 1234);
+        assert_eq!(replay.scenario, "sandbox");
+        assert_eq!(replay.map_path, "maps/sandbox.map");
+        assert_eq!(
+            replay.entries,
+            vec![
+                ReplayEntry {
+                    round: 1,
+                    turn: 1,
+                    command: Command::StartMoving,
+                },
+                ReplayEntry {
+                    round: 1,
+                    turn: 1,
+                    command: Command::Step(IVec2::new(1, 0)),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_broken_replays() {
+        assert!(Replay::parse("1 1 start_moving\n").is_err());
+        assert!(Replay::parse("seed 1\n1 1 teleport\n").is_err());
+        assert!(Replay::parse("seed 1\n1 step 1 0\n").is_err());
+    }
+}
@@ -1,12 +1,65 @@
+use crate::assets::*;
+use crate::debug_log::*;
 use macroquad::audio::*;
 use macroquad::prelude::*;
 use std::collections::HashMap;
 
 pub type SoundAtlas = HashMap<String, Sound>;
 
-pub async fn make() -> SoundAtlas {
+///Loads every sound listed in the manifest. Sounds that fail to load are logged and left out of the atlas,
+///the audio system treats them the same as any other missing sound. Several keys can share a file, which is
+///only loaded the once
+pub async fn make(manifest: &AssetManifest, debug_log: &mut DebugLogger) -> SoundAtlas {
     set_pc_assets_folder("resources");
-    let chime = load_sound("sounds/chime.wav").await.unwrap();
-    let sound_atlas = HashMap::from([(String::from("chime"), chime)]);
-    return sound_atlas;
+    let mut sound_atlas = SoundAtlas::new();
+    let mut loaded: HashMap<String, Sound> = HashMap::new();
+    for entry in manifest.sounds() {
+        if let Some(sound) = loaded.get(&entry.path) {
+            sound_atlas.insert(entry.key.clone(), sound.clone());
+            continue;
+        }
+        match load_sound(&entry.path).await {
+            Ok(sound) => {
+                loaded.insert(entry.path.clone(), sound.clone());
+                sound_atlas.insert(entry.key.clone(), sound);
+            }
+            Err(error) => debug_log.log(
+                DebugLevel::Error,
+                Subsystem::Assets,
+                &format!(
+                    "failed to load sound \"{}\" from {}: {}",
+                    entry.key, entry.path, error
+                ),
+            ),
+        }
+    }
+    sound_atlas
+}
+
+///Reloads every sound that comes from a sound file that's changed on disk. If the file can't be loaded the
+///sounds that were already there are left alone
+pub async fn reload_sound(
+    sound_atlas: &mut SoundAtlas,
+    manifest: &AssetManifest,
+    path: &str,
+    debug_log: &mut DebugLogger,
+) {
+    set_pc_assets_folder("resources");
+    for entry in manifest.sounds().filter(|entry| entry.path == path) {
+        match load_sound(&entry.path).await {
+            Ok(sound) => {
+                sound_atlas.insert(entry.key.clone(), sound);
+                debug_log.log(
+                    DebugLevel::Info,
+                    Subsystem::Assets,
+                    &format!("reloaded sound \"{}\" from {}", entry.key, path),
+                );
+            }
+            Err(error) => debug_log.log(
+                DebugLevel::Error,
+                Subsystem::Assets,
+                &format!("failed to reload sound {}: {}", path, error),
+            ),
+        }
+    }
 }
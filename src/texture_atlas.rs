@@ -1,40 +1,197 @@
+use crate::assets::*;
+use crate::debug_log::*;
 use macroquad::prelude::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-pub type TextureAtlas = HashMap<String, Texture2D>;
+///A texture along with the part of it to draw, for sprites that live on a larger sheet
+#[derive(Clone, Debug)]
+pub struct Sprite {
+    pub texture: Texture2D,
+    pub source: Option<Rect>,
+}
 
-pub async fn make() -> TextureAtlas {
+///Every texture the game has loaded, stored under the keys from the asset manifest. Anything that's asked for
+///but couldn't be loaded is drawn with a placeholder so it's obvious on screen rather than crashing the game
+pub struct TextureAtlas {
+    sprites: HashMap<String, Sprite>,
+    animations: HashMap<String, AnimationDef>,
+    placeholder: Texture2D,
+    ///keys that have been asked for but aren't loaded, remembered so they're only reported once
+    reported_missing: HashSet<String>,
+}
+impl TextureAtlas {
+    ///returns the sprite stored under a key, or the placeholder if there isn't one
+    pub fn sprite(&self, key: &str) -> Sprite {
+        match self.sprites.get(key) {
+            Some(sprite) => sprite.clone(),
+            None => Sprite {
+                texture: self.placeholder.clone(),
+                source: None,
+            },
+        }
+    }
+    pub fn contains(&self, key: &str) -> bool {
+        self.sprites.contains_key(key)
+    }
+    ///Returns true the first time a key that isn't in the atlas is asked about, so the caller can report it
+    ///without flooding the log every frame it's drawn
+    pub fn report_missing(&mut self, key: &str) -> bool {
+        !self.contains(key) && self.reported_missing.insert(key.to_string())
+    }
+    ///Draws the sprite stored under a key at a position on the screen
+    pub fn draw(&self, key: &str, x: f32, y: f32, color: Color) {
+        self.draw_ex(key, x, y, color, DrawTextureParams::default());
+    }
+    ///Draws the sprite stored under a key with extra parameters (eg. flipping), filling in its sheet rectangle
+    ///unless the parameters already ask for a specific one
+    pub fn draw_ex(&self, key: &str, x: f32, y: f32, color: Color, params: DrawTextureParams) {
+        let sprite = self.sprite(key);
+        let params = DrawTextureParams {
+            source: params.source.or(sprite.source),
+            ..params
+        };
+        draw_texture_ex(&sprite.texture, x, y, color, params);
+    }
+    ///Returns the sprite key to show for an animation that's been playing for `elapsed` seconds, or None if there's
+    ///no animation under that key. Looping animations wrap around, the rest hold on their last frame
+    pub fn animation_frame(&self, key: &str, elapsed: f32) -> Option<&str> {
+        let animation = self.animations.get(key)?;
+        let frame = (elapsed / animation.frame_time) as usize;
+        let frame = if animation.looping {
+            frame % animation.frames.len()
+        } else {
+            frame.min(animation.frames.len() - 1)
+        };
+        Some(&animation.frames[frame])
+    }
+    ///checks if a one shot animation has played through all of its frames. Looping animations never finish
+    pub fn animation_finished(&self, key: &str, elapsed: f32) -> bool {
+        match self.animations.get(key) {
+            Some(animation) => {
+                !animation.looping
+                    && elapsed >= animation.frame_time * animation.frames.len() as f32
+            }
+            None => true,
+        }
+    }
+    ///adds or replaces the sprite stored under a key
+    pub fn insert(&mut self, key: String, sprite: Sprite) {
+        self.sprites.insert(key, sprite);
+    }
+}
+
+///Loads every texture listed in the manifest. Textures that fail to load are logged and left out of the atlas
+///so the placeholder is drawn in their place
+pub async fn make(manifest: &AssetManifest, debug_log: &mut DebugLogger) -> TextureAtlas {
     set_pc_assets_folder("resources");
-    let default_character = load_texture("textures/default_character.png")
-        .await
-        .expect("Failed to load texture.");
-    let default_floor = load_texture("textures/default_floor.png")
-        .await
-        .expect("Failed to load texture.");
-    let default_fullcover = load_texture("textures/default_fullcover.png")
-        .await
-        .expect("Failed to load texture.");
-    let default_halfcover = load_texture("textures/default_halfcover.png")
-        .await
-        .expect("Failed to load texture.");
-    let default_quartercover = load_texture("textures/default_quartercover.png")
-        .await
-        .expect("Failed to load texture.");
-    let default_wall = load_texture("textures/default_wall.png")
-        .await
-        .expect("Failed to load texture.");
-    let down_arrow = load_texture("textures/down_arrow.png")
-        .await
-        .expect("Failed to load texture.");
-    let texture_atlas = HashMap::from([
-        (String::from("character"), default_character),
-        (String::from("floor"), default_floor),
-        (String::from("full cover"), default_fullcover),
-        (String::from("half cover"), default_halfcover),
-        (String::from("quarter cover"), default_quartercover),
-        (String::from("wall"), default_wall),
-        (String::from("down arrow"), down_arrow),
-    ]);
+    let mut sprites: HashMap<String, Sprite> = HashMap::new();
+    //sprite sheets are listed once per sprite on them, so keep hold of what's been loaded to only load each file once
+    let mut loaded: HashMap<String, Texture2D> = HashMap::new();
+    for entry in manifest.textures() {
+        let texture = match loaded.get(&entry.path) {
+            Some(texture) => Some(texture.clone()),
+            None => match load_texture(&entry.path).await {
+                Ok(texture) => {
+                    loaded.insert(entry.path.clone(), texture.clone());
+                    Some(texture)
+                }
+                Err(error) => {
+                    debug_log.log(
+                        DebugLevel::Error,
+                        Subsystem::Assets,
+                        &format!(
+                            "failed to load texture \"{}\" from {}: {}",
+                            entry.key, entry.path, error
+                        ),
+                    );
+                    None
+                }
+            },
+        };
+        if let Some(texture) = texture {
+            sprites.insert(
+                entry.key.clone(),
+                Sprite {
+                    texture,
+                    source: entry.source,
+                },
+            );
+        }
+    }
+    let mut animations: HashMap<String, AnimationDef> = HashMap::new();
+    for animation in manifest.animations.iter() {
+        for frame in animation.frames.iter() {
+            if !sprites.contains_key(frame) {
+                debug_log.log(
+                    DebugLevel::Error,
+                    Subsystem::Assets,
+                    &format!(
+                        "animation \"{}\" uses \"{}\" which isn't a loaded texture",
+                        animation.key, frame
+                    ),
+                );
+            }
+        }
+        animations.insert(animation.key.clone(), animation.clone());
+    }
     build_textures_atlas();
-    return texture_atlas;
+    TextureAtlas {
+        sprites,
+        animations,
+        placeholder: make_placeholder(),
+        reported_missing: HashSet::new(),
+    }
+}
+
+///Reloads every sprite that comes from a texture file that's changed on disk, keeping their sheet rectangles.
+///If the file can't be loaded the sprites that were already there are left alone
+pub async fn reload_texture(
+    atlas: &mut TextureAtlas,
+    manifest: &AssetManifest,
+    path: &str,
+    debug_log: &mut DebugLogger,
+) {
+    set_pc_assets_folder("resources");
+    let texture = match load_texture(path).await {
+        Ok(texture) => texture,
+        Err(error) => {
+            debug_log.log(
+                DebugLevel::Error,
+                Subsystem::Assets,
+                &format!("failed to reload texture {}: {}", path, error),
+            );
+            return;
+        }
+    };
+    for entry in manifest.textures().filter(|entry| entry.path == path) {
+        atlas.insert(
+            entry.key.clone(),
+            Sprite {
+                texture: texture.clone(),
+                source: entry.source,
+            },
+        );
+        atlas.reported_missing.remove(&entry.key);
+    }
+    debug_log.log(
+        DebugLevel::Info,
+        Subsystem::Assets,
+        &format!("reloaded texture {}", path),
+    );
+}
+
+///Makes the magenta and black checkerboard drawn in place of any texture that's missing
+pub fn make_placeholder() -> Texture2D {
+    let size = 32;
+    let mut image = Image::gen_image_color(size, size, MAGENTA);
+    for y in 0..size {
+        for x in 0..size {
+            if (x / 8 + y / 8) % 2 == 0 {
+                image.set_pixel(x as u32, y as u32, BLACK);
+            }
+        }
+    }
+    let texture = Texture2D::from_image(&image);
+    texture.set_filter(FilterMode::Nearest);
+    texture
 }